@@ -4,11 +4,18 @@ use std::{
 };
 
 use cofield_receiver::{
-    flex_sensor_glove::FlexSensorGlove, FlexSensorGloveNotification, MeanAggregator, MovingFingers,
-    Opt, Process, TextPattern,
+    check_resample_rate, flex_sensor_glove::FlexSensorGlove, ActivityGate, ActivityGateConfig,
+    ArtifactDetector, Calibration, CalibrationEvent, CharacterTable, ChordDecoder, ClassifierModel,
+    ConfusionModel, DecoderArming, EpisodeExtractor, FingerEvidence, FingersSensibility,
+    FlexSensorGloveNotification, GestureRecognizer, GestureTemplate, GloveProfile, LanguageLayer,
+    MeanAggregator, MessageFraming, MessageFramingConfig, MorseConfig, MorseDecoder, MovingFingers,
+    Opt, PatternConfig, PatternDefinition, PatternEngine, PatternSpec, Process, ProcessEvent,
+    QuestionEvent, QuestionList, QuestionSession, SignalQualityMonitor, TextMode, TextPattern,
+    TrainingEvent, TrainingHistory, TrainingSession, TranscriptWriter, WordList,
+    DEFAULT_CALIBRATION_FLEX_MS, DEFAULT_CALIBRATION_REST_MS,
 };
-use serde::Serialize;
-use tauri::{AppHandle, Emitter, State};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::{sync::Mutex, task::JoinHandle};
 
 struct GloveProcess {
@@ -16,6 +23,20 @@ struct GloveProcess {
     text_patterns: Arc<Mutex<Option<TextPattern>>>,
     aggregator: Arc<Mutex<Option<MeanAggregator>>>,
     raw_output_writer: Arc<Mutex<Option<csv::Writer<std::fs::File>>>>,
+    transcript_writer: Arc<Mutex<Option<TranscriptWriter>>>,
+    calibration: Arc<Mutex<Option<Calibration>>>,
+    artifact_detector: Arc<Mutex<Option<ArtifactDetector>>>,
+    signal_quality_monitor: Arc<Mutex<Option<SignalQualityMonitor>>>,
+    gesture_recognizer: Arc<Mutex<Option<GestureRecognizer>>>,
+    activity_gate: Arc<Mutex<Option<ActivityGate>>>,
+    decoder_arming: Arc<Mutex<Option<DecoderArming>>>,
+    pattern_engine: Arc<Mutex<Option<PatternEngine>>>,
+    training_session: Arc<Mutex<Option<TrainingSession>>>,
+    question_session: Arc<Mutex<Option<QuestionSession>>>,
+    language_layer: Arc<Mutex<Option<LanguageLayer>>>,
+    message_framing: Arc<Mutex<Option<MessageFraming>>>,
+    fingers_sensibility: FingersSensibility,
+    resample_rate: Option<f64>,
 }
 
 pub struct ProcessHandle {
@@ -24,7 +45,60 @@ pub struct ProcessHandle {
 
 pub struct ProcessConfig {
     aggregation_size: Mutex<usize>,
+    resample_rate: Mutex<Option<f64>>,
     use_keyboard_emulation: Mutex<bool>,
+    profile: Mutex<Option<GloveProfile>>,
+    reject_artifacts: Mutex<bool>,
+    mask_bad_channels: Mutex<bool>,
+    use_activity_gate: Mutex<bool>,
+    classifier_model: Mutex<Option<PathBuf>>,
+    gestures: Mutex<Option<Vec<GestureTemplate>>>,
+    arming_signal: Mutex<Option<ArmingSignalConfig>>,
+    patterns: Mutex<Vec<PatternDefinition>>,
+    character_table: Mutex<CharacterTable>,
+    text_mode: Mutex<TextModeConfig>,
+    word_list: Mutex<Option<WordList>>,
+    message_framing: Mutex<Option<MessageFramingConfig>>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArmingSignalConfig {
+    fingers_order: Vec<u8>,
+    repetitions: u32,
+    window_ms: u32,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(
+    tag = "mode",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TextModeConfig {
+    #[default]
+    Positions,
+    Morse(MorseConfig),
+    Chord {
+        settle_ms: u32,
+    },
+}
+
+impl TextModeConfig {
+    fn create_text_mode(&self) -> TextMode {
+        match self {
+            TextModeConfig::Positions => TextMode::Positions,
+            TextModeConfig::Morse(config) => TextMode::Morse(MorseDecoder::new(*config)),
+            TextModeConfig::Chord { settle_ms } => TextMode::Chord(ChordDecoder::new(*settle_ms)),
+        }
+    }
+}
+
+impl ArmingSignalConfig {
+    fn create_decoder_arming(&self) -> Result<DecoderArming, String> {
+        DecoderArming::new(self.fingers_order.clone(), self.repetitions, self.window_ms)
+            .map_err(|e| e.to_string())
+    }
 }
 
 impl ProcessHandle {
@@ -46,9 +120,41 @@ impl ProcessConfig {
     pub fn new() -> Self {
         Self {
             aggregation_size: Opt::default().aggregation_size.into(),
+            resample_rate: Opt::default().resample_rate.into(),
             use_keyboard_emulation: true.into(),
+            profile: None.into(),
+            reject_artifacts: Opt::default().reject_artifacts.into(),
+            mask_bad_channels: false.into(),
+            use_activity_gate: Opt::default().activity_gate.into(),
+            classifier_model: None.into(),
+            gestures: None.into(),
+            arming_signal: None.into(),
+            patterns: vec![].into(),
+            character_table: CharacterTable::default().into(),
+            text_mode: TextModeConfig::default().into(),
+            word_list: None.into(),
+            message_framing: None.into(),
         }
     }
+
+    /// The confusion model follows the character table and the text mode
+    async fn create_language_layer(&self) -> Option<LanguageLayer> {
+        let word_list = self.word_list.lock().await.clone()?;
+        let confusion_model = ConfusionModel::new(
+            &*self.character_table.lock().await,
+            &self.text_mode.lock().await.create_text_mode(),
+        );
+
+        Some(LanguageLayer::new(word_list, confusion_model))
+    }
+
+    /// The check symbols follow the character table
+    async fn create_message_framing(&self) -> Option<MessageFraming> {
+        let config = self.message_framing.lock().await.clone()?;
+        let character_table = self.character_table.lock().await.clone();
+
+        Some(MessageFraming::new(config, character_table))
+    }
 }
 
 #[tauri::command]
@@ -65,6 +171,35 @@ pub async fn start_listening_glove(
     opt.verbose = true;
     opt.aggregation_size = *process_config.aggregation_size.lock().await;
 
+    let mut crosstalk_compensation = None;
+    if let Some(profile) = process_config.profile.lock().await.as_ref() {
+        opt.fingers_sensibility = profile.fingers_sensibility;
+        crosstalk_compensation = profile
+            .crosstalk_compensation()
+            .map_err(|e| e.to_string())?;
+    }
+
+    opt.reject_artifacts = *process_config.reject_artifacts.lock().await;
+    opt.mask_bad_channels = *process_config.mask_bad_channels.lock().await;
+    opt.activity_gate = *process_config.use_activity_gate.lock().await;
+    opt.resample_rate = *process_config.resample_rate.lock().await;
+    opt.classifier_model = process_config.classifier_model.lock().await.clone();
+
+    let resampler = opt.get_resampler().map_err(|e| e.to_string())?;
+    let movement_detector = opt.get_movement_detector().map_err(|e| e.to_string())?;
+    let artifact_detector = opt.get_artifact_detector().map_err(|e| e.to_string())?;
+    let activity_gate = opt.get_activity_gate().map_err(|e| e.to_string())?;
+    let pattern_engine = create_pattern_engine(&process_config.patterns.lock().await)?;
+    let decoder_arming = process_config
+        .arming_signal
+        .lock()
+        .await
+        .as_ref()
+        .map(ArmingSignalConfig::create_decoder_arming)
+        .transpose()?;
+    let fingers_sensibility = opt.fingers_sensibility;
+    let resample_rate = opt.resample_rate;
+
     let app_text = app.clone();
     let mut text_patterns = TextPattern::new(Box::new(move |str| {
         app_text.emit("new_character", str).ok();
@@ -72,14 +207,48 @@ pub async fn start_listening_glove(
 
     let use_keyboard_emulation = *process_config.use_keyboard_emulation.lock().await;
     text_patterns.use_keyboard_emulation(use_keyboard_emulation);
+    text_patterns.set_character_table(process_config.character_table.lock().await.clone());
+    text_patterns.set_mode(process_config.text_mode.lock().await.create_text_mode());
+    text_patterns.set_finger_evidence(Some(FingerEvidence::new(&fingers_sensibility)));
 
     let aggregator = Arc::new(Mutex::new(Some(MeanAggregator::new(opt.aggregation_size))));
     let text_patterns = Arc::new(Mutex::new(Some(text_patterns)));
     let raw_output_writer = Arc::new(Mutex::new(None::<csv::Writer<std::fs::File>>));
+    let transcript_writer = Arc::new(Mutex::new(None));
+    let calibration = Arc::new(Mutex::new(None));
+    let training_session = Arc::new(Mutex::new(None));
+    let question_session = Arc::new(Mutex::new(None));
+    let language_layer = Arc::new(Mutex::new(process_config.create_language_layer().await));
+    let message_framing = Arc::new(Mutex::new(process_config.create_message_framing().await));
+    let artifact_detector = Arc::new(Mutex::new(artifact_detector));
+    let activity_gate = Arc::new(Mutex::new(activity_gate));
+    let decoder_arming = Arc::new(Mutex::new(decoder_arming));
+    let pattern_engine = Arc::new(Mutex::new(pattern_engine));
+    let signal_quality_monitor = Arc::new(Mutex::new(Some(opt.get_signal_quality_monitor())));
+    let gesture_recognizer = Arc::new(Mutex::new(
+        process_config
+            .gestures
+            .lock()
+            .await
+            .clone()
+            .map(GestureRecognizer::new),
+    ));
 
     let process_aggregator = aggregator.clone();
     let process_text_patterns = text_patterns.clone();
     let process_raw_output_writer = raw_output_writer.clone();
+    let process_transcript_writer = transcript_writer.clone();
+    let process_calibration = calibration.clone();
+    let process_training_session = training_session.clone();
+    let process_question_session = question_session.clone();
+    let process_language_layer = language_layer.clone();
+    let process_message_framing = message_framing.clone();
+    let process_artifact_detector = artifact_detector.clone();
+    let process_signal_quality_monitor = signal_quality_monitor.clone();
+    let process_gesture_recognizer = gesture_recognizer.clone();
+    let process_activity_gate = activity_gate.clone();
+    let process_decoder_arming = decoder_arming.clone();
+    let process_pattern_engine = pattern_engine.clone();
 
     let handle = tokio::spawn(async move {
         let flex_sensor_glove = FlexSensorGlove::new(&opt)
@@ -99,9 +268,74 @@ pub async fn start_listening_glove(
 
         let mut process = Process::new(notification_stream, opt.fingers_sensibility).await;
 
+        process.set_resampler(resampler);
+        process.set_movement_detector(movement_detector);
+        process.set_crosstalk_compensation(crosstalk_compensation);
         process.set_aggregator(process_aggregator);
         process.set_text_pattern_detection(process_text_patterns);
         process.set_raw_output_writer(process_raw_output_writer);
+        process.set_transcript_writer(process_transcript_writer);
+        process.set_calibration(process_calibration);
+        process.set_training_session(process_training_session);
+        process.set_question_session(process_question_session.clone());
+        process.set_language_layer(process_language_layer);
+        process.set_message_framing(process_message_framing);
+        process.set_artifact_detector(process_artifact_detector);
+        process.set_signal_quality_monitor(process_signal_quality_monitor);
+        process.set_gesture_recognizer(process_gesture_recognizer);
+        process.set_activity_gate(process_activity_gate);
+        process.set_decoder_arming(process_decoder_arming);
+        process.set_pattern_engine(process_pattern_engine);
+        process.set_episode_extractor(Arc::new(Mutex::new(Some(EpisodeExtractor::new()))));
+
+        let app_event = app.clone();
+        process.on_event(move |event| {
+            match &event.event {
+                ProcessEvent::ChannelQualityChanged { .. } => {
+                    app_event.emit("channel_quality", event).ok();
+                }
+                ProcessEvent::MovementEpisode(episode) => {
+                    app_event.emit("movement_episode", episode).ok();
+                }
+                ProcessEvent::GateChanged { state, .. } => {
+                    app_event.emit("gate_changed", state).ok();
+                }
+                ProcessEvent::DecoderArmed { until } => {
+                    app_event.emit("decoder_armed", until).ok();
+                }
+                ProcessEvent::DecoderDisarmed => {
+                    app_event.emit("decoder_disarmed", ()).ok();
+                }
+                ProcessEvent::PatternDetected { .. } => {
+                    app_event.emit("pattern_detected", event).ok();
+                }
+                ProcessEvent::PatternProgress(progress) => {
+                    app_event.emit("pattern_progress", progress).ok();
+                }
+                ProcessEvent::TextProgress(progress) => {
+                    app_event.emit("text_progress", progress).ok();
+                }
+                ProcessEvent::CharacterTyped(character) => {
+                    app_event.emit("character_typed", character).ok();
+                }
+                ProcessEvent::WordDecoded { .. } => {
+                    app_event.emit("word_decoded", event).ok();
+                }
+                ProcessEvent::WordCompletions { .. } => {
+                    app_event.emit("word_completions", event).ok();
+                }
+                ProcessEvent::MessageStarted => {
+                    app_event.emit("message_started", ()).ok();
+                }
+                ProcessEvent::MessageReceived(message) => {
+                    app_event.emit("message_received", message).ok();
+                }
+                _ => {}
+            }
+
+            app_event.emit("process_event", event).ok();
+        });
+
         process.on_notification(move |notification, moved_fingers| {
             app.emit(
                 "glove_notification",
@@ -113,10 +347,14 @@ pub async fn start_listening_glove(
             .ok();
         });
 
-        process
-            .run()
-            .await
-            .expect("An error occured while running process");
+        let result = process.run().await;
+
+        // Saves the answers given so far when the glove disconnects or the process fails
+        if let Some(mut question_session) = process_question_session.lock().await.take() {
+            question_session.finish(chrono::Local::now());
+        }
+
+        result.expect("An error occured while running process");
     });
 
     *process_handle.process.lock().await = Some(GloveProcess {
@@ -124,6 +362,20 @@ pub async fn start_listening_glove(
         text_patterns,
         aggregator,
         raw_output_writer,
+        transcript_writer,
+        calibration,
+        artifact_detector,
+        signal_quality_monitor,
+        gesture_recognizer,
+        activity_gate,
+        decoder_arming,
+        pattern_engine,
+        training_session,
+        question_session,
+        language_layer,
+        message_framing,
+        fingers_sensibility,
+        resample_rate,
     });
 
     Ok(())
@@ -139,6 +391,11 @@ pub async fn stop_listening_glove(
         return Ok(());
     };
 
+    // The aborted task never reaches its own finish, the answers are written here
+    if let Some(mut question_session) = glove_process.question_session.lock().await.take() {
+        question_session.finish(chrono::Local::now());
+    }
+
     glove_process.process.abort();
 
     app.emit("glove_disconnected", ()).ok();
@@ -159,6 +416,18 @@ pub async fn set_aggregation_size(
     if aggregation_size == *process_config.aggregation_size.lock().await {
         return Ok(());
     }
+
+    // The classifier features depend on the aggregation the model was trained with
+    if let Some(path) = process_config.classifier_model.lock().await.as_deref() {
+        let model = ClassifierModel::load(path).map_err(|e| e.to_string())?;
+        if model.aggregation_size != aggregation_size {
+            return Err(format!(
+                "The classifier model was trained with an aggregation size of {}",
+                model.aggregation_size
+            ));
+        }
+    }
+
     *process_config.aggregation_size.lock().await = aggregation_size;
 
     let mut process = process_handle.process.lock().await;
@@ -176,6 +445,34 @@ pub async fn set_aggregation_size(
     Ok(())
 }
 
+/// Like the classifier model, the rate is used the next time the glove is connected
+#[tauri::command]
+pub async fn set_resample_rate(
+    process_config: State<'_, ProcessConfig>,
+    resample_rate: Option<f64>,
+) -> Result<(), String> {
+    if resample_rate.is_some_and(|rate| !rate.is_finite() || rate <= 0.0) {
+        return Err("The resample rate must be positive".to_string());
+    }
+
+    // The classifier features and the gesture samples depend on the rate they were made at
+    if let Some(path) = process_config.classifier_model.lock().await.as_deref() {
+        let model = ClassifierModel::load(path).map_err(|e| format!("{e:#}"))?;
+        check_resample_rate("classifier model", model.resample_rate, resample_rate)
+            .map_err(|e| format!("{e:#}"))?;
+    }
+
+    for template in process_config.gestures.lock().await.iter().flatten() {
+        template
+            .check_resample_rate(resample_rate)
+            .map_err(|e| format!("{e:#}"))?;
+    }
+
+    *process_config.resample_rate.lock().await = resample_rate;
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn set_keyboard_emulation_config(
     process_handle: State<'_, ProcessHandle>,
@@ -203,6 +500,293 @@ pub async fn set_keyboard_emulation_config(
     Ok(())
 }
 
+/// `name_or_path` is a built in table (en, fr, de) or a toml/json file
+#[tauri::command]
+pub async fn set_character_table(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    name_or_path: String,
+) -> Result<CharacterTable, String> {
+    let character_table =
+        CharacterTable::load_or_builtin(&name_or_path).map_err(|e| format!("{e:#}"))?;
+
+    *process_config.character_table.lock().await = character_table.clone();
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(character_table);
+    };
+
+    glove_process
+        .text_patterns
+        .lock()
+        .await
+        .as_mut()
+        .map(|text_patterns| text_patterns.set_character_table(character_table.clone()));
+
+    *glove_process.language_layer.lock().await = process_config.create_language_layer().await;
+    *glove_process.message_framing.lock().await = process_config.create_message_framing().await;
+
+    Ok(character_table)
+}
+
+/// Messages are framed by patterns set with `set_patterns` or `load_pattern_config`,
+/// without config the typed characters are not grouped in messages
+#[tauri::command]
+pub async fn set_message_framing(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    config: Option<MessageFramingConfig>,
+) -> Result<(), String> {
+    if let Some(config) = &config {
+        let patterns = process_config.patterns.lock().await;
+        let pattern_names: Vec<&str> = patterns
+            .iter()
+            .map(|pattern| pattern.name.as_str())
+            .collect();
+
+        config
+            .validate(&pattern_names)
+            .map_err(|e| format!("{e:#}"))?;
+    }
+
+    *process_config.message_framing.lock().await = config;
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    *glove_process.message_framing.lock().await = process_config.create_message_framing().await;
+
+    Ok(())
+}
+
+/// `name_or_path` is a built in word list (en, fr, de) or a text file with one word per line,
+/// without word list the decoded words are neither completed nor corrected
+#[tauri::command]
+pub async fn set_word_list(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    name_or_path: Option<String>,
+) -> Result<(), String> {
+    let word_list = name_or_path
+        .as_deref()
+        .map(WordList::load_or_builtin)
+        .transpose()
+        .map_err(|e| format!("{e:#}"))?;
+
+    *process_config.word_list.lock().await = word_list;
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    *glove_process.language_layer.lock().await = process_config.create_language_layer().await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_text_mode(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    text_mode: TextModeConfig,
+) -> Result<(), String> {
+    if let TextModeConfig::Morse(config) = &text_mode {
+        config.validate().map_err(|e| e.to_string())?;
+    }
+
+    *process_config.text_mode.lock().await = text_mode;
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    glove_process
+        .text_patterns
+        .lock()
+        .await
+        .as_mut()
+        .map(|text_patterns| text_patterns.set_mode(text_mode.create_text_mode()));
+
+    *glove_process.language_layer.lock().await = process_config.create_language_layer().await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_artifact_rejection_config(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    is_enabled: bool,
+) -> Result<(), String> {
+    if is_enabled == *process_config.reject_artifacts.lock().await {
+        return Ok(());
+    }
+
+    *process_config.reject_artifacts.lock().await = is_enabled;
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    *glove_process.artifact_detector.lock().await = is_enabled.then(|| {
+        ArtifactDetector::new(
+            &glove_process.fingers_sensibility,
+            Opt::default().get_artifact_detector_config(),
+        )
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_activity_gate_config(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    is_enabled: bool,
+) -> Result<(), String> {
+    if is_enabled == *process_config.use_activity_gate.lock().await {
+        return Ok(());
+    }
+
+    *process_config.use_activity_gate.lock().await = is_enabled;
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    *glove_process.activity_gate.lock().await = is_enabled.then(|| {
+        ActivityGate::new(
+            &glove_process.fingers_sensibility,
+            ActivityGateConfig::default(),
+        )
+    });
+
+    Ok(())
+}
+
+/// Without arming signal, text is always decoded
+#[tauri::command]
+pub async fn set_arming_signal_config(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    arming_signal: Option<ArmingSignalConfig>,
+) -> Result<(), String> {
+    let decoder_arming = arming_signal
+        .as_ref()
+        .map(ArmingSignalConfig::create_decoder_arming)
+        .transpose()?;
+
+    *process_config.arming_signal.lock().await = arming_signal;
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    *glove_process.decoder_arming.lock().await = decoder_arming;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_patterns(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    patterns: Vec<PatternSpec>,
+) -> Result<(), String> {
+    let definitions = patterns.iter().map(PatternDefinition::from).collect();
+
+    apply_pattern_definitions(&process_handle, &process_config, definitions).await
+}
+
+#[tauri::command]
+pub async fn load_pattern_config(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    file_path: Option<PathBuf>,
+    allow_commands: bool,
+) -> Result<Option<PatternConfig>, String> {
+    let config = file_path
+        .as_deref()
+        .map(PatternConfig::load)
+        .transpose()
+        .map_err(|e| format!("{e:#}"))?;
+
+    for definition in config.iter().flat_map(|config| &config.patterns) {
+        definition
+            .check_commands_allowed(allow_commands)
+            .map_err(|e| format!("{e:#}"))?;
+    }
+
+    let definitions = config
+        .as_ref()
+        .map(|config| config.patterns.clone())
+        .unwrap_or_default();
+
+    apply_pattern_definitions(&process_handle, &process_config, definitions).await?;
+
+    Ok(config)
+}
+
+fn create_pattern_engine(
+    definitions: &[PatternDefinition],
+) -> Result<Option<PatternEngine>, String> {
+    (!definitions.is_empty())
+        .then(|| PatternEngine::from_definitions(definitions))
+        .transpose()
+        .map_err(|e| format!("{e:#}"))
+}
+
+/// Patterns are replaced while the glove is connected
+async fn apply_pattern_definitions(
+    process_handle: &ProcessHandle,
+    process_config: &ProcessConfig,
+    definitions: Vec<PatternDefinition>,
+) -> Result<(), String> {
+    let pattern_engine = create_pattern_engine(&definitions)?;
+
+    *process_config.patterns.lock().await = definitions;
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    *glove_process.pattern_engine.lock().await = pattern_engine;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_mask_bad_channels_config(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    is_enabled: bool,
+) -> Result<(), String> {
+    *process_config.mask_bad_channels.lock().await = is_enabled;
+
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    glove_process
+        .signal_quality_monitor
+        .lock()
+        .await
+        .as_mut()
+        .map(|monitor| monitor.set_mask_bad_channels(is_enabled));
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn set_output_raw_data(
     process_handle: State<'_, ProcessHandle>,
@@ -213,12 +797,10 @@ pub async fn set_output_raw_data(
         return Ok(None);
     };
 
-    let file_path = folder_path.map(|path| {
-        let timestamp = chrono::Local::now().format("%Y-%m-%d-%H-%M-%S").to_string();
-        let file_name = format!("raw_{timestamp}.csv");
-
-        Path::new(&path).join(file_name)
-    });
+    let timestamp = chrono::Local::now().format("%Y-%m-%d-%H-%M-%S").to_string();
+    let file_path = folder_path
+        .as_ref()
+        .map(|path| Path::new(path).join(format!("raw_{timestamp}.csv")));
 
     let writer = match &file_path {
         Some(file_path) => {
@@ -232,7 +814,313 @@ pub async fn set_output_raw_data(
         None => None,
     };
 
+    // The transcript of the decoded text is written alongside the raw recording
+    let transcript_writer = folder_path
+        .map(|path| {
+            TranscriptWriter::create(&Path::new(&path).join(format!("transcript_{timestamp}")))
+        })
+        .transpose()
+        .map_err(|e| format!("{e:#}"))?;
+
     *glove_process.raw_output_writer.lock().await = writer;
+    *glove_process.transcript_writer.lock().await = transcript_writer;
 
     Ok(file_path)
 }
+
+#[tauri::command]
+pub async fn load_glove_profile(
+    process_config: State<'_, ProcessConfig>,
+    file_path: Option<PathBuf>,
+) -> Result<Option<GloveProfile>, String> {
+    let profile = file_path
+        .map(|path| GloveProfile::load(&path))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    // The profile is applied the next time the glove is connected
+    *process_config.profile.lock().await = profile.clone();
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn load_classifier_model(
+    process_config: State<'_, ProcessConfig>,
+    file_path: Option<PathBuf>,
+) -> Result<Option<ClassifierModel>, String> {
+    let model = file_path
+        .as_deref()
+        .map(ClassifierModel::load)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let aggregation_size = *process_config.aggregation_size.lock().await;
+    if let Some(model) = model
+        .as_ref()
+        .filter(|model| model.aggregation_size != aggregation_size)
+    {
+        return Err(format!(
+            "The classifier model was trained with an aggregation size of {}, not {aggregation_size}",
+            model.aggregation_size
+        ));
+    }
+
+    if let Some(model) = model.as_ref() {
+        check_resample_rate(
+            "classifier model",
+            model.resample_rate,
+            *process_config.resample_rate.lock().await,
+        )
+        .map_err(|e| format!("{e:#}"))?;
+    }
+
+    // Like the glove profile, the model is used the next time the glove is connected
+    *process_config.classifier_model.lock().await = file_path;
+
+    Ok(model)
+}
+
+#[tauri::command]
+pub async fn load_gestures(
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    file_path: Option<PathBuf>,
+) -> Result<Option<Vec<GestureTemplate>>, String> {
+    let gestures = file_path
+        .as_deref()
+        .map(GestureTemplate::load_all)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let process = process_handle.process.lock().await;
+
+    // The connected glove keeps the rate it was started with
+    let mut resample_rates = vec![*process_config.resample_rate.lock().await];
+    resample_rates.extend(
+        process
+            .as_ref()
+            .map(|glove_process| glove_process.resample_rate),
+    );
+
+    for template in gestures.iter().flatten() {
+        for resample_rate in &resample_rates {
+            template
+                .check_resample_rate(*resample_rate)
+                .map_err(|e| format!("{e:#}"))?;
+        }
+    }
+
+    *process_config.gestures.lock().await = gestures.clone();
+
+    // Unlike the classifier model, gestures can be replaced while the glove is connected
+    if let Some(glove_process) = process.as_ref() {
+        *glove_process.gesture_recognizer.lock().await =
+            gestures.clone().map(GestureRecognizer::new);
+    }
+
+    Ok(gestures)
+}
+
+#[tauri::command]
+pub async fn start_calibration(
+    app: AppHandle,
+    process_handle: State<'_, ProcessHandle>,
+    profile_name: String,
+    folder_path: String,
+) -> Result<(), String> {
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Err("The glove must be connected to start a calibration".to_string());
+    };
+
+    GloveProfile::validate_name(&profile_name).map_err(|e| format!("{e:#}"))?;
+
+    let mut calibration = Calibration::new(
+        profile_name,
+        DEFAULT_CALIBRATION_REST_MS,
+        DEFAULT_CALIBRATION_FLEX_MS,
+    );
+
+    calibration.on_event(move |event| {
+        app.emit("calibration_event", event).ok();
+
+        if let CalibrationEvent::Finished(profile) = event {
+            // The process applies the profile to its detection, it is also kept for the next connection
+            let app_profile = app.clone();
+            let new_profile = *profile.clone();
+            tauri::async_runtime::spawn(async move {
+                let fingers_sensibility = new_profile.fingers_sensibility;
+                *app_profile.state::<ProcessConfig>().profile.lock().await = Some(new_profile);
+
+                let process_handle = app_profile.state::<ProcessHandle>();
+                if let Some(glove_process) = process_handle.process.lock().await.as_mut() {
+                    glove_process.fingers_sensibility = fingers_sensibility;
+                }
+            });
+
+            let saved = profile
+                .default_path(Path::new(&folder_path))
+                .and_then(|file_path| profile.save(&file_path).map(|_| file_path));
+
+            match saved {
+                Ok(file_path) => app.emit("calibration_saved", file_path).ok(),
+                Err(err) => {
+                    let event = CalibrationEvent::Failed(format!("{err:#}"));
+                    app.emit("calibration_event", event).ok()
+                }
+            };
+        }
+    });
+
+    *glove_process.calibration.lock().await = Some(calibration);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_calibration(process_handle: State<'_, ProcessHandle>) -> Result<(), String> {
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    *glove_process.calibration.lock().await = None;
+
+    Ok(())
+}
+
+/// `targets` are characters or words typed one after the other with the current character table
+#[tauri::command]
+pub async fn start_training(
+    app: AppHandle,
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    participant: String,
+    targets: Vec<String>,
+    folder_path: String,
+) -> Result<(), String> {
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Err("The glove must be connected to start a training".to_string());
+    };
+
+    let character_table = process_config.character_table.lock().await;
+    let mut training_session = TrainingSession::new(participant, &targets, &character_table)
+        .map_err(|e| format!("{e:#}"))?;
+
+    training_session.on_event(move |event| {
+        app.emit("training_event", event).ok();
+
+        if let TrainingEvent::Finished(report) = event {
+            match TrainingHistory::append(Path::new(&folder_path), *report.clone()) {
+                Ok(file_path) => app.emit("training_saved", file_path).ok(),
+                Err(err) => app.emit("training_save_failed", format!("{err:#}")).ok(),
+            };
+        }
+    });
+
+    *glove_process.training_session.lock().await = Some(training_session);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_training(process_handle: State<'_, ProcessHandle>) -> Result<(), String> {
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    *glove_process.training_session.lock().await = None;
+
+    Ok(())
+}
+
+/// Random characters of the default layer of the current character table
+#[tauri::command]
+pub async fn random_training_targets(
+    process_config: State<'_, ProcessConfig>,
+    count: usize,
+) -> Result<Vec<String>, String> {
+    let character_table = process_config.character_table.lock().await;
+
+    Ok(TrainingSession::random_characters(&character_table, count))
+}
+
+/// Questions are answered in order, the answers being written to a csv file in `folder_path`
+/// once every question is answered or the session is stopped
+#[tauri::command]
+pub async fn start_question_session(
+    app: AppHandle,
+    process_handle: State<'_, ProcessHandle>,
+    process_config: State<'_, ProcessConfig>,
+    participant: String,
+    file_path: PathBuf,
+    folder_path: String,
+) -> Result<QuestionList, String> {
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Err("The glove must be connected to start a question session".to_string());
+    };
+
+    TrainingHistory::validate_participant(&participant).map_err(|e| format!("{e:#}"))?;
+
+    let question_list = QuestionList::load(&file_path).map_err(|e| format!("{e:#}"))?;
+    question_list
+        .validate(&process_config.patterns.lock().await)
+        .map_err(|e| format!("{e:#}"))?;
+
+    let mut question_session =
+        QuestionSession::new(participant.clone(), &question_list).map_err(|e| format!("{e:#}"))?;
+    question_session.set_finger_evidence(Some(FingerEvidence::new(
+        &glove_process.fingers_sensibility,
+    )));
+
+    question_session.on_event(move |event| {
+        app.emit("question_event", event).ok();
+
+        if let QuestionEvent::Finished(report) = event {
+            let timestamp = report.finished_at.format("%Y-%m-%d-%H-%M-%S").to_string();
+            let file_path =
+                Path::new(&folder_path).join(format!("{participant}_answers_{timestamp}.csv"));
+
+            match report.write_csv(&file_path) {
+                Ok(()) => app.emit("question_answers_saved", file_path).ok(),
+                Err(err) => app
+                    .emit("question_answers_save_failed", format!("{err:#}"))
+                    .ok(),
+            };
+        }
+    });
+
+    *glove_process.question_session.lock().await = Some(question_session);
+
+    Ok(question_list)
+}
+
+/// Saves the answers given so far
+#[tauri::command]
+pub async fn stop_question_session(process_handle: State<'_, ProcessHandle>) -> Result<(), String> {
+    let mut process = process_handle.process.lock().await;
+    let Some(glove_process) = process.as_mut() else {
+        return Ok(());
+    };
+
+    if let Some(mut question_session) = glove_process.question_session.lock().await.take() {
+        question_session.finish(chrono::Local::now());
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_training_history(
+    participant: String,
+    folder_path: String,
+) -> Result<TrainingHistory, String> {
+    let file_path = TrainingHistory::default_path(Path::new(&folder_path), &participant)
+        .map_err(|e| format!("{e:#}"))?;
+
+    TrainingHistory::load_or_default(&file_path, &participant).map_err(|e| format!("{e:#}"))
+}
@@ -13,8 +13,30 @@ pub fn run() {
             commands::start_listening_glove,
             commands::stop_listening_glove,
             commands::set_aggregation_size,
+            commands::set_resample_rate,
             commands::set_keyboard_emulation_config,
+            commands::set_character_table,
+            commands::set_text_mode,
+            commands::set_word_list,
+            commands::set_message_framing,
+            commands::set_artifact_rejection_config,
+            commands::set_activity_gate_config,
+            commands::set_arming_signal_config,
+            commands::set_patterns,
+            commands::load_pattern_config,
+            commands::set_mask_bad_channels_config,
             commands::set_output_raw_data,
+            commands::load_glove_profile,
+            commands::load_classifier_model,
+            commands::load_gestures,
+            commands::start_calibration,
+            commands::cancel_calibration,
+            commands::start_training,
+            commands::cancel_training,
+            commands::random_training_targets,
+            commands::load_training_history,
+            commands::start_question_session,
+            commands::stop_question_session,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Local};
+
+use crate::{events::ProcessEvent, opt::FingersSensibility, parser::FingersFlexValues};
+
+/// Highest value of the ESP32 12 bits ADC
+pub const ADC_MAX_VALUE: u32 = 4095;
+
+pub const DEFAULT_ARTIFACT_DEVIATION_FACTOR: f32 = 3.0;
+pub const DEFAULT_ARTIFACT_MIN_CHANNELS: usize = 4;
+pub const DEFAULT_ARTIFACT_HOLD_OFF_MS: u32 = 2000;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ArtifactDetectorConfig {
+    /// A channel deviates when its aggregated value is above `sensibility * deviation_factor`
+    pub deviation_factor: f32,
+    /// How many channels must deviate or saturate at the same time to detect an artifact
+    pub min_channels: usize,
+    /// How long after the last deviation the artifact is considered over
+    pub hold_off_ms: u32,
+}
+
+impl Default for ArtifactDetectorConfig {
+    fn default() -> Self {
+        Self {
+            deviation_factor: DEFAULT_ARTIFACT_DEVIATION_FACTOR,
+            min_channels: DEFAULT_ARTIFACT_MIN_CHANNELS,
+            hold_off_ms: DEFAULT_ARTIFACT_HOLD_OFF_MS,
+        }
+    }
+}
+
+/// Detects whole-hand or body movements, like a sleeper turning over,
+/// during which finger movements must not be decoded
+pub struct ArtifactDetector {
+    config: ArtifactDetectorConfig,
+    deviation_thresholds: [u32; 5],
+
+    artifact_started_at: Option<DateTime<Local>>,
+    last_deviation_time: Option<DateTime<Local>>,
+}
+
+impl ArtifactDetector {
+    pub fn new(sensibility: &FingersSensibility, config: ArtifactDetectorConfig) -> Self {
+        Self {
+            deviation_thresholds: sensibility
+                .0
+                .map(|sensibility| (sensibility as f32 * config.deviation_factor) as u32),
+            config,
+
+            artifact_started_at: None,
+            last_deviation_time: None,
+        }
+    }
+
+    /// Scales the thresholds to a new calibration, with the configured deviation factor
+    pub fn set_sensibility(&mut self, sensibility: &FingersSensibility) {
+        self.deviation_thresholds = sensibility
+            .0
+            .map(|sensibility| (sensibility as f32 * self.config.deviation_factor) as u32);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.artifact_started_at.is_some()
+    }
+
+    /// Returns an event when an artifact episode starts or ends
+    pub fn process(
+        &mut self,
+        raw_values: &FingersFlexValues,
+        aggregated_values: &FingersFlexValues,
+        time: DateTime<Local>,
+    ) -> Option<ProcessEvent> {
+        if self.is_deviating(raw_values, aggregated_values) {
+            self.last_deviation_time = Some(time);
+
+            if self.artifact_started_at.is_none() {
+                self.artifact_started_at = Some(time);
+                return Some(ProcessEvent::ArtifactStarted);
+            }
+
+            return None;
+        }
+
+        let (Some(started_at), Some(last_deviation_time)) =
+            (self.artifact_started_at, self.last_deviation_time)
+        else {
+            return None;
+        };
+
+        let elapsed_ms = time
+            .signed_duration_since(last_deviation_time)
+            .num_milliseconds();
+
+        if elapsed_ms <= self.config.hold_off_ms as i64 {
+            return None;
+        }
+
+        self.artifact_started_at = None;
+
+        Some(ProcessEvent::ArtifactEnded {
+            duration_ms: time.signed_duration_since(started_at).num_milliseconds(),
+        })
+    }
+
+    fn is_deviating(
+        &self,
+        raw_values: &FingersFlexValues,
+        aggregated_values: &FingersFlexValues,
+    ) -> bool {
+        let deviating_channels = (0..5)
+            .filter(|&i| {
+                aggregated_values.0[i] > self.deviation_thresholds[i]
+                    || raw_values.0[i] >= ADC_MAX_VALUE
+            })
+            .count();
+
+        deviating_channels >= self.config.min_channels
+    }
+}
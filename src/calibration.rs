@@ -0,0 +1,262 @@
+use anyhow::bail;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    crosstalk::{estimate_coupling, CrosstalkCompensation},
+    opt::FingersSensibility,
+    parser::{FingersFlexValues, FlexSensorGloveNotification},
+    profile::{FingerCalibration, GloveProfile},
+};
+
+pub const FINGER_NAMES: [&str; 5] = ["thumb", "index", "middle", "ring", "little"];
+
+pub const DEFAULT_CALIBRATION_REST_MS: u32 = 5000;
+pub const DEFAULT_CALIBRATION_FLEX_MS: u32 = 5000;
+
+/// Samples received right after a step starts are ignored to leave time to the participant to react
+const STEP_SETTLE_MS: i64 = 500;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "step",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CalibrationStep {
+    /// The participant keeps the whole hand relaxed
+    Rest { duration_ms: u32 },
+    /// The participant flexes and releases a single finger a few times
+    Flex { finger: usize, duration_ms: u32 },
+}
+
+impl CalibrationStep {
+    pub fn duration_ms(&self) -> u32 {
+        match self {
+            CalibrationStep::Rest { duration_ms } => *duration_ms,
+            CalibrationStep::Flex { duration_ms, .. } => *duration_ms,
+        }
+    }
+
+    pub fn instruction(&self) -> String {
+        match self {
+            CalibrationStep::Rest { duration_ms } => format!(
+                "Relax your hand and keep it still for {} seconds",
+                duration_ms / 1000
+            ),
+            CalibrationStep::Flex {
+                finger,
+                duration_ms,
+            } => format!(
+                "Flex and release your {} finger a few times for {} seconds",
+                FINGER_NAMES[*finger],
+                duration_ms / 1000
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum CalibrationEvent {
+    StepStarted(CalibrationStep),
+    Finished(Box<GloveProfile>),
+    Failed(String),
+}
+
+pub type CalibrationEventFn = Box<dyn Fn(&CalibrationEvent) + Send + Sync>;
+
+#[derive(Default)]
+struct StepSamples {
+    raw: Vec<FingersFlexValues>,
+    aggregated: Vec<FingersFlexValues>,
+}
+
+/// Guides the participant through a rest period followed by one flex period per finger,
+/// and computes a [`GloveProfile`] from the recorded samples.
+pub struct Calibration {
+    profile_name: String,
+
+    steps: Vec<CalibrationStep>,
+    samples: Vec<StepSamples>,
+    current_step: usize,
+    step_started_at: Option<DateTime<Local>>,
+    profile: Option<GloveProfile>,
+
+    on_event: Option<CalibrationEventFn>,
+}
+
+impl Calibration {
+    pub fn new(profile_name: String, rest_ms: u32, flex_ms: u32) -> Self {
+        let mut steps = vec![CalibrationStep::Rest {
+            duration_ms: rest_ms,
+        }];
+
+        steps.extend((0..5).map(|finger| CalibrationStep::Flex {
+            finger,
+            duration_ms: flex_ms,
+        }));
+
+        Self {
+            profile_name,
+
+            samples: steps.iter().map(|_| StepSamples::default()).collect(),
+            steps,
+            current_step: 0,
+            step_started_at: None,
+            profile: None,
+
+            on_event: None,
+        }
+    }
+
+    pub fn on_event(&mut self, closure: impl Fn(&CalibrationEvent) + Send + Sync + 'static) {
+        self.on_event = Some(Box::new(closure))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.current_step >= self.steps.len()
+    }
+
+    /// The profile computed once every step is done
+    pub fn take_profile(&mut self) -> Option<GloveProfile> {
+        self.profile.take()
+    }
+
+    pub fn current_step(&self) -> Option<&CalibrationStep> {
+        self.steps.get(self.current_step)
+    }
+
+    /// Records a sample, `raw_values` being the values before aggregation
+    pub fn push(
+        &mut self,
+        raw_values: &FingersFlexValues,
+        aggregated_notification: &FlexSensorGloveNotification,
+    ) {
+        if self.is_done() {
+            return;
+        }
+
+        let time = aggregated_notification.dt;
+        let step_started_at = match self.step_started_at {
+            Some(step_started_at) => step_started_at,
+            None => {
+                self.step_started_at = Some(time);
+                self.emit(&CalibrationEvent::StepStarted(
+                    self.steps[self.current_step],
+                ));
+                time
+            }
+        };
+
+        let elapsed_ms = time
+            .signed_duration_since(step_started_at)
+            .num_milliseconds();
+
+        if elapsed_ms >= self.steps[self.current_step].duration_ms() as i64 {
+            self.next_step(time);
+            return;
+        }
+
+        if elapsed_ms >= STEP_SETTLE_MS {
+            let samples = &mut self.samples[self.current_step];
+            samples.raw.push(*raw_values);
+            samples.aggregated.push(aggregated_notification.flex_values);
+        }
+    }
+
+    fn next_step(&mut self, time: DateTime<Local>) {
+        self.current_step += 1;
+
+        if let Some(step) = self.current_step().copied() {
+            self.step_started_at = Some(time);
+            self.emit(&CalibrationEvent::StepStarted(step));
+            return;
+        }
+
+        match self.compute_profile() {
+            Ok(profile) => {
+                self.emit(&CalibrationEvent::Finished(Box::new(profile.clone())));
+                self.profile = Some(profile);
+            }
+            Err(err) => self.emit(&CalibrationEvent::Failed(err.to_string())),
+        }
+    }
+
+    fn emit(&self, event: &CalibrationEvent) {
+        if let Some(on_event) = self.on_event.as_ref() {
+            on_event(event)
+        }
+    }
+
+    pub fn compute_profile(&self) -> anyhow::Result<GloveProfile> {
+        let rest = &self.samples[0];
+        if rest.raw.is_empty() {
+            bail!("no sample was recorded during the rest period");
+        }
+
+        let flex_samples: Vec<_> = self.samples[1..]
+            .iter()
+            .map(|samples| samples.aggregated.clone())
+            .collect();
+        let coupling = estimate_coupling(&flex_samples);
+
+        // Detection compares the compensated values to the sensibility,
+        // so the noise and peaks are measured after compensation as well
+        let crosstalk_compensation = CrosstalkCompensation::new(&coupling).ok();
+        let compensate = |values: &FingersFlexValues| match &crosstalk_compensation {
+            Some(crosstalk_compensation) => crosstalk_compensation.apply(values),
+            None => *values,
+        };
+
+        let mut fingers = [FingerCalibration::default(); 5];
+        let mut fingers_sensibility = [0; 5];
+
+        for (i, finger) in fingers.iter_mut().enumerate() {
+            let flex = &self.samples[i + 1];
+            if flex.raw.is_empty() {
+                bail!(
+                    "no sample was recorded while flexing the {} finger",
+                    FINGER_NAMES[i]
+                );
+            }
+
+            let baseline =
+                rest.raw.iter().map(|v| v.0[i] as u64).sum::<u64>() / rest.raw.len() as u64;
+            let max_raw = flex.raw.iter().map(|v| v.0[i]).max().unwrap_or_default();
+
+            finger.baseline = baseline as u32;
+            finger.range = max_raw.saturating_sub(finger.baseline);
+            finger.noise = rest
+                .aggregated
+                .iter()
+                .map(|v| compensate(v).0[i])
+                .max()
+                .unwrap_or_default();
+            finger.peak = flex
+                .aggregated
+                .iter()
+                .map(|v| compensate(v).0[i])
+                .max()
+                .unwrap_or_default();
+
+            if finger.peak <= finger.noise {
+                bail!(
+                    "the {} finger movement could not be distinguished from the rest noise",
+                    FINGER_NAMES[i]
+                );
+            }
+
+            // Halfway between the rest noise and the flex peak
+            fingers_sensibility[i] = finger.noise + (finger.peak - finger.noise) / 2;
+        }
+
+        Ok(GloveProfile {
+            name: self.profile_name.clone(),
+            created_at: Local::now(),
+            fingers,
+            fingers_sensibility: FingersSensibility(fingers_sensibility),
+            crosstalk: crosstalk_compensation.map(|_| coupling),
+        })
+    }
+}
@@ -0,0 +1,301 @@
+use std::{collections::VecDeque, path::Path};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aggregator::MeanAggregator,
+    crosstalk::CrosstalkCompensation,
+    detection::MovementDetector,
+    parser::{FingersFlexValues, FlexSensorGloveNotification, MovingFingers},
+    resampler::Resampler,
+};
+
+pub const DEFAULT_CLASSIFIER_WINDOW_SIZE: usize = 10;
+pub const DEFAULT_CLASSIFIER_EPOCHS: usize = 300;
+
+const NUMBER_OF_FEATURES: usize = 4;
+const LEARNING_RATE: f32 = 0.1;
+
+type Features = [f32; NUMBER_OF_FEATURES];
+
+/// A labelled movement of a finger in a recording, fingers are indexed from 0 (thumb) to 4
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovementLabel {
+    pub finger: usize,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+impl MovementLabel {
+    /// Reads a `finger,start,end` csv file with headers
+    pub fn read_csv(path: &Path) -> anyhow::Result<Vec<Self>> {
+        let mut reader = csv::Reader::from_path(path)
+            .with_context(|| format!("unable to open labels {}", path.display()))?;
+
+        let labels = reader
+            .deserialize()
+            .collect::<Result<Vec<Self>, _>>()
+            .with_context(|| format!("invalid labels {}", path.display()))?;
+
+        if let Some(label) = labels.iter().find(|label| label.finger > 4) {
+            bail!("invalid finger {} in labels, expected 0 to 4", label.finger);
+        }
+
+        Ok(labels)
+    }
+}
+
+/// Amplitude, slope, variance and mean of the last aggregated values of a finger
+fn compute_features(window: &VecDeque<u32>) -> Features {
+    let len = window.len() as f32;
+    let first = *window.front().unwrap_or(&0) as f32;
+    let last = *window.back().unwrap_or(&0) as f32;
+
+    let mean = window.iter().map(|v| *v as f32).sum::<f32>() / len;
+    let variance = window
+        .iter()
+        .map(|v| (*v as f32 - mean).powi(2))
+        .sum::<f32>()
+        / len;
+    let slope = (last - first) / (len - 1.0).max(1.0);
+
+    [last, slope, variance, mean]
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogisticRegression {
+    pub weights: Features,
+    pub bias: f32,
+    pub feature_means: Features,
+    pub feature_stds: Features,
+}
+
+impl LogisticRegression {
+    fn normalize(&self, features: &Features) -> Features {
+        let mut normalized = [0.0; NUMBER_OF_FEATURES];
+        for (i, value) in normalized.iter_mut().enumerate() {
+            *value = (features[i] - self.feature_means[i]) / self.feature_stds[i];
+        }
+        normalized
+    }
+
+    pub fn predict(&self, features: &Features) -> f32 {
+        let normalized = self.normalize(features);
+        let z: f32 = self
+            .weights
+            .iter()
+            .zip(normalized.iter())
+            .map(|(w, x)| w * x)
+            .sum::<f32>()
+            + self.bias;
+
+        1.0 / (1.0 + (-z).exp())
+    }
+
+    /// Batch gradient descent, positive samples are weighted to balance the classes
+    fn train(samples: &[(Features, bool)], epochs: usize) -> Self {
+        let len = samples.len() as f32;
+        let mut model = Self::default();
+
+        for i in 0..NUMBER_OF_FEATURES {
+            let mean = samples.iter().map(|(f, _)| f[i]).sum::<f32>() / len;
+            let variance = samples
+                .iter()
+                .map(|(f, _)| (f[i] - mean).powi(2))
+                .sum::<f32>()
+                / len;
+
+            model.feature_means[i] = mean;
+            model.feature_stds[i] = variance.sqrt().max(f32::EPSILON);
+        }
+
+        let positives = samples.iter().filter(|(_, label)| *label).count().max(1) as f32;
+        let positive_weight = (len - positives).max(1.0) / positives;
+
+        let normalized: Vec<(Features, f32, f32)> = samples
+            .iter()
+            .map(|(features, label)| {
+                let (target, weight) = if *label {
+                    (1.0, positive_weight)
+                } else {
+                    (0.0, 1.0)
+                };
+                (model.normalize(features), target, weight)
+            })
+            .collect();
+
+        for _ in 0..epochs {
+            let mut weights_gradient = [0.0; NUMBER_OF_FEATURES];
+            let mut bias_gradient = 0.0;
+
+            for (features, target, weight) in &normalized {
+                let z: f32 = model
+                    .weights
+                    .iter()
+                    .zip(features.iter())
+                    .map(|(w, x)| w * x)
+                    .sum::<f32>()
+                    + model.bias;
+                let error = (1.0 / (1.0 + (-z).exp()) - target) * weight;
+
+                for (gradient, x) in weights_gradient.iter_mut().zip(features.iter()) {
+                    *gradient += error * x;
+                }
+                bias_gradient += error;
+            }
+
+            for (w, gradient) in model.weights.iter_mut().zip(weights_gradient.iter()) {
+                *w -= LEARNING_RATE * gradient / len;
+            }
+            model.bias -= LEARNING_RATE * bias_gradient / len;
+        }
+
+        model
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassifierModel {
+    /// Number of aggregated samples the features are computed on
+    pub window_size: usize,
+    /// Aggregation size the model was trained with, detection should use the same
+    pub aggregation_size: usize,
+    /// Rate the recording was resampled at before training, detection should use the same
+    #[serde(default)]
+    pub resample_rate: Option<f64>,
+    /// Probability above which a finger is considered moving
+    pub threshold: f32,
+    pub fingers: [LogisticRegression; 5],
+}
+
+impl ClassifierModel {
+    /// Trains one model per finger from a raw recording and the movements labelled in it.
+    /// The recording is resampled, aggregated and compensated for crosstalk as it is before detection
+    pub fn train(
+        recording: &[FlexSensorGloveNotification],
+        labels: &[MovementLabel],
+        window_size: usize,
+        mut resampler: Option<Resampler>,
+        aggregation_size: usize,
+        crosstalk_compensation: Option<&CrosstalkCompensation>,
+        epochs: usize,
+    ) -> anyhow::Result<Self> {
+        if window_size < 2 {
+            bail!("the window size must be at least 2");
+        }
+
+        let resample_rate = resampler.as_ref().map(Resampler::rate);
+        let recording = match resampler.as_mut() {
+            Some(resampler) => resampler.resample(recording),
+            None => recording.to_vec(),
+        };
+
+        let mut aggregator = (aggregation_size > 0).then(|| MeanAggregator::new(aggregation_size));
+        let mut windows: [VecDeque<u32>; 5] = Default::default();
+        let mut samples: [Vec<(Features, bool)>; 5] = Default::default();
+
+        for notification in recording {
+            let mut notification = match aggregator.as_mut() {
+                Some(aggregator) => aggregator.push_and_aggregate(notification),
+                None => notification,
+            };
+            if let Some(crosstalk_compensation) = crosstalk_compensation {
+                notification.flex_values = crosstalk_compensation.apply(&notification.flex_values);
+            }
+
+            for (finger, window) in windows.iter_mut().enumerate() {
+                push_window(window, notification.flex_values.0[finger], window_size);
+
+                if window.len() < window_size {
+                    continue;
+                }
+
+                let is_moving = labels.iter().any(|label| {
+                    label.finger == finger
+                        && label.start <= notification.dt
+                        && notification.dt <= label.end
+                });
+
+                samples[finger].push((compute_features(window), is_moving));
+            }
+        }
+
+        let mut fingers: [LogisticRegression; 5] = Default::default();
+        for (finger, model) in fingers.iter_mut().enumerate() {
+            if !samples[finger].iter().any(|(_, is_moving)| *is_moving) {
+                bail!("no labelled movement found in the recording for finger {finger}");
+            }
+
+            *model = LogisticRegression::train(&samples[finger], epochs);
+        }
+
+        Ok(Self {
+            window_size,
+            aggregation_size,
+            resample_rate,
+            threshold: 0.5,
+            fingers,
+        })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("unable to open classifier model {}", path.display()))?;
+
+        serde_json::from_reader(file)
+            .with_context(|| format!("invalid classifier model {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("unable to create classifier model {}", path.display()))?;
+
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+fn push_window(window: &mut VecDeque<u32>, value: u32, window_size: usize) {
+    if window.len() >= window_size {
+        window.pop_front();
+    }
+    window.push_back(value);
+}
+
+/// Detects movements with a trained [`ClassifierModel`] instead of thresholds
+pub struct ClassifierDetector {
+    model: ClassifierModel,
+    windows: [VecDeque<u32>; 5],
+}
+
+impl ClassifierDetector {
+    pub fn new(model: ClassifierModel) -> Self {
+        Self {
+            model,
+            windows: Default::default(),
+        }
+    }
+}
+
+impl MovementDetector for ClassifierDetector {
+    fn detect(&mut self, values: &FingersFlexValues, _time: DateTime<Local>) -> MovingFingers {
+        let mut moved_fingers = [false; 5];
+
+        for (finger, window) in self.windows.iter_mut().enumerate() {
+            push_window(window, values.0[finger], self.model.window_size);
+
+            if window.len() < self.model.window_size {
+                continue;
+            }
+
+            let probability = self.model.fingers[finger].predict(&compute_features(window));
+            moved_fingers[finger] = probability > self.model.threshold;
+        }
+
+        moved_fingers
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::bail;
+
+use crate::parser::FingersFlexValues;
+
+/// `coupling[source][target]` is the part of the `source` finger movement
+/// that is also measured by the `target` finger sensor
+pub type CouplingMatrix = [[f32; 5]; 5];
+
+/// Estimates the coupling matrix from single-finger flexes,
+/// `flex_samples[i]` being the aggregated values recorded while only the finger `i` was flexed
+pub fn estimate_coupling(flex_samples: &[Vec<FingersFlexValues>]) -> CouplingMatrix {
+    let mut coupling = [[0.0; 5]; 5];
+
+    for (source, samples) in flex_samples.iter().enumerate().take(5) {
+        let source_energy: f32 = samples.iter().map(|v| (v.0[source] as f32).powi(2)).sum();
+
+        for (target, factor) in coupling[source].iter_mut().enumerate() {
+            if target == source {
+                *factor = 1.0;
+                continue;
+            }
+
+            if source_energy == 0.0 {
+                continue;
+            }
+
+            // Least squares slope of the target values against the source values
+            let covariance: f32 = samples
+                .iter()
+                .map(|v| v.0[source] as f32 * v.0[target] as f32)
+                .sum();
+
+            *factor = (covariance / source_energy).clamp(0.0, 0.95);
+        }
+    }
+
+    coupling
+}
+
+/// Removes the part of each sensor value caused by the movement of the other fingers
+pub struct CrosstalkCompensation {
+    unmixing: [[f32; 5]; 5],
+}
+
+impl CrosstalkCompensation {
+    pub fn new(coupling: &CouplingMatrix) -> anyhow::Result<Self> {
+        // observed[target] = sum(coupling[source][target] * actual[source])
+        let mut mixing = [[0.0; 5]; 5];
+        for (source, row) in coupling.iter().enumerate() {
+            for (target, value) in row.iter().enumerate() {
+                mixing[target][source] = *value;
+            }
+        }
+
+        let Some(unmixing) = invert(mixing) else {
+            bail!("the crosstalk coupling matrix is not invertible");
+        };
+
+        Ok(Self { unmixing })
+    }
+
+    pub fn apply(&self, values: &FingersFlexValues) -> FingersFlexValues {
+        let mut result = FingersFlexValues([0; 5]);
+
+        for (i, row) in self.unmixing.iter().enumerate() {
+            let value: f32 = row
+                .iter()
+                .zip(values.0.iter())
+                .map(|(factor, value)| factor * *value as f32)
+                .sum();
+
+            result.0[i] = value.max(0.0).round() as u32;
+        }
+
+        result
+    }
+}
+
+/// Gauss-Jordan elimination with partial pivoting
+fn invert(mut matrix: [[f32; 5]; 5]) -> Option<[[f32; 5]; 5]> {
+    let mut inverse = [[0.0; 5]; 5];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+
+    for column in 0..5 {
+        let pivot = (column..5).max_by(|a, b| {
+            matrix[*a][column]
+                .abs()
+                .total_cmp(&matrix[*b][column].abs())
+        })?;
+
+        if matrix[pivot][column].abs() < f32::EPSILON {
+            return None;
+        }
+
+        matrix.swap(column, pivot);
+        inverse.swap(column, pivot);
+
+        let pivot_value = matrix[column][column];
+        for i in 0..5 {
+            matrix[column][i] /= pivot_value;
+            inverse[column][i] /= pivot_value;
+        }
+
+        for row in 0..5 {
+            if row == column {
+                continue;
+            }
+
+            let factor = matrix[row][column];
+            for i in 0..5 {
+                matrix[row][i] -= factor * matrix[column][i];
+                inverse[row][i] -= factor * inverse[column][i];
+            }
+        }
+    }
+
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invert_gives_the_inverse() {
+        let mut matrix = [[0.0; 5]; 5];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] = 1.0;
+            if i + 1 < 5 {
+                row[i + 1] = 0.3;
+            }
+        }
+
+        let inverse = invert(matrix).unwrap();
+
+        for (i, row) in matrix.iter().enumerate() {
+            for j in 0..5 {
+                let product: f32 = row
+                    .iter()
+                    .zip(&inverse)
+                    .map(|(value, inverse_row)| value * inverse_row[j])
+                    .sum();
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product - expected).abs() < 1e-5, "({i}, {j}) is {product}");
+            }
+        }
+    }
+
+    #[test]
+    fn invert_rejects_a_singular_matrix() {
+        let mut matrix = [[0.0; 5]; 5];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        matrix[4] = matrix[3];
+
+        assert!(invert(matrix).is_none());
+    }
+
+    #[test]
+    fn estimate_coupling_measures_the_neighbour_part() {
+        // The index moves alone and the middle finger sensor sees half of it
+        let mut flex_samples = vec![vec![]; 5];
+        flex_samples[1] = (1..=10)
+            .map(|value| FingersFlexValues([0, value * 100, value * 50, 0, 0]))
+            .collect();
+
+        let coupling = estimate_coupling(&flex_samples);
+
+        assert_eq!(coupling[1][1], 1.0);
+        assert!((coupling[1][2] - 0.5).abs() < 1e-5);
+        assert_eq!(coupling[1][0], 0.0);
+        // Fingers without samples are only coupled to themselves
+        assert_eq!(coupling[0], [1.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn compensation_removes_the_estimated_coupling() {
+        let mut flex_samples = vec![vec![]; 5];
+        flex_samples[1] = vec![FingersFlexValues([0, 200, 100, 0, 0])];
+
+        let compensation = CrosstalkCompensation::new(&estimate_coupling(&flex_samples)).unwrap();
+
+        assert_eq!(
+            compensation
+                .apply(&FingersFlexValues([0, 200, 100, 0, 0]))
+                .0,
+            [0, 200, 0, 0, 0]
+        );
+    }
+}
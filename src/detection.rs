@@ -0,0 +1,224 @@
+use chrono::{DateTime, Local};
+
+use crate::{
+    opt::FingersSensibility,
+    parser::{FingersFlexValues, MovingFingers},
+};
+
+/// A release ratio of 1 gives the single threshold detection of earlier versions
+pub const DEFAULT_RELEASE_RATIO: f32 = 0.7;
+/// No debouncing by default, a finger moves as soon as its value is above its sensibility
+pub const DEFAULT_MIN_ON_MS: u32 = 0;
+pub const DEFAULT_REFRACTORY_MS: u32 = 0;
+
+#[derive(Debug, Copy, Clone)]
+pub struct FingerDetectionConfig {
+    /// The finger starts moving when its value goes above this threshold
+    pub on_threshold: u32,
+    /// The finger stops moving when its value goes back below or equal to this threshold
+    pub off_threshold: u32,
+    /// How long the value must stay above `on_threshold` before the finger is considered moving
+    pub min_on_ms: u32,
+    /// How long after a release a new movement of the same finger is ignored
+    pub refractory_ms: u32,
+}
+
+impl FingerDetectionConfig {
+    pub fn from_sensibility(
+        sensibility: u32,
+        release_ratio: f32,
+        min_on_ms: u32,
+        refractory_ms: u32,
+    ) -> Self {
+        Self {
+            on_threshold: sensibility,
+            off_threshold: (sensibility as f32 * release_ratio.clamp(0.0, 1.0)) as u32,
+            min_on_ms,
+            refractory_ms,
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct FingerState {
+    is_moving: bool,
+    above_since: Option<DateTime<Local>>,
+    released_at: Option<DateTime<Local>>,
+}
+
+pub trait MovementDetector {
+    fn detect(&mut self, values: &FingersFlexValues, time: DateTime<Local>) -> MovingFingers;
+
+    /// Applies the thresholds of a new calibration, detectors without thresholds ignore them
+    fn set_sensibility(&mut self, _sensibility: &FingersSensibility) {}
+}
+
+pub type MovementDetectorDyn = Box<dyn MovementDetector + Send + Sync>;
+
+/// Stateful threshold detection with separate on/off thresholds,
+/// a minimum on-time and a refractory period for each finger
+pub struct HysteresisDetector {
+    configs: [FingerDetectionConfig; 5],
+    /// Kept apart from the integer thresholds so that a new sensibility does not make it drift
+    release_ratios: [f32; 5],
+    states: [FingerState; 5],
+}
+
+impl HysteresisDetector {
+    pub fn new(configs: [FingerDetectionConfig; 5]) -> Self {
+        Self {
+            release_ratios: configs
+                .map(|config| config.off_threshold as f32 / config.on_threshold.max(1) as f32),
+            configs,
+            states: [FingerState::default(); 5],
+        }
+    }
+
+    pub fn from_sensibility(
+        sensibility: &FingersSensibility,
+        release_ratio: f32,
+        min_on_ms: u32,
+        refractory_ms: u32,
+    ) -> Self {
+        let mut detector = Self::new(sensibility.0.map(|sensibility| {
+            FingerDetectionConfig::from_sensibility(
+                sensibility,
+                release_ratio,
+                min_on_ms,
+                refractory_ms,
+            )
+        }));
+        detector.release_ratios = [release_ratio.clamp(0.0, 1.0); 5];
+
+        detector
+    }
+
+    pub fn configs(&self) -> &[FingerDetectionConfig; 5] {
+        &self.configs
+    }
+}
+
+impl MovementDetector for HysteresisDetector {
+    fn detect(&mut self, values: &FingersFlexValues, time: DateTime<Local>) -> MovingFingers {
+        let mut moved_fingers = [false; 5];
+
+        for (i, moved) in moved_fingers.iter_mut().enumerate() {
+            *moved = update_finger_state(&mut self.states[i], &self.configs[i], values.0[i], time);
+        }
+
+        moved_fingers
+    }
+
+    /// Keeps the release ratio of each finger
+    fn set_sensibility(&mut self, sensibility: &FingersSensibility) {
+        for ((config, release_ratio), sensibility) in self
+            .configs
+            .iter_mut()
+            .zip(self.release_ratios)
+            .zip(sensibility.0)
+        {
+            config.on_threshold = sensibility;
+            config.off_threshold = (sensibility as f32 * release_ratio) as u32;
+        }
+    }
+}
+
+fn update_finger_state(
+    state: &mut FingerState,
+    config: &FingerDetectionConfig,
+    value: u32,
+    time: DateTime<Local>,
+) -> bool {
+    if state.is_moving {
+        if value <= config.off_threshold {
+            state.is_moving = false;
+            state.above_since = None;
+            state.released_at = Some(time);
+        }
+
+        return state.is_moving;
+    }
+
+    let in_refractory_period = state
+        .released_at
+        .is_some_and(|released_at| elapsed_ms(released_at, time) < config.refractory_ms as i64);
+
+    if in_refractory_period || value <= config.on_threshold {
+        state.above_since = None;
+        return false;
+    }
+
+    let above_since = *state.above_since.get_or_insert(time);
+    state.is_moving = elapsed_ms(above_since, time) >= config.min_on_ms as i64;
+
+    state.is_moving
+}
+
+fn elapsed_ms(since: DateTime<Local>, time: DateTime<Local>) -> i64 {
+    time.signed_duration_since(since).num_milliseconds()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const CONFIG: FingerDetectionConfig = FingerDetectionConfig {
+        on_threshold: 100,
+        off_threshold: 50,
+        min_on_ms: 30,
+        refractory_ms: 100,
+    };
+
+    fn at(ms: i64) -> DateTime<Local> {
+        Local.timestamp_millis_opt(1_000_000 + ms).unwrap()
+    }
+
+    #[test]
+    fn moves_once_above_the_threshold_long_enough() {
+        let mut state = FingerState::default();
+
+        assert!(!update_finger_state(&mut state, &CONFIG, 100, at(0)));
+        assert!(!update_finger_state(&mut state, &CONFIG, 101, at(10)));
+        assert!(!update_finger_state(&mut state, &CONFIG, 150, at(30)));
+        assert!(update_finger_state(&mut state, &CONFIG, 150, at(40)));
+    }
+
+    #[test]
+    fn short_spike_is_ignored() {
+        let mut state = FingerState::default();
+
+        assert!(!update_finger_state(&mut state, &CONFIG, 150, at(0)));
+        assert!(!update_finger_state(&mut state, &CONFIG, 80, at(20)));
+        // The time above the threshold starts again
+        assert!(!update_finger_state(&mut state, &CONFIG, 150, at(40)));
+        assert!(!update_finger_state(&mut state, &CONFIG, 150, at(60)));
+        assert!(update_finger_state(&mut state, &CONFIG, 150, at(70)));
+    }
+
+    #[test]
+    fn keeps_moving_between_the_thresholds() {
+        let mut state = FingerState::default();
+        update_finger_state(&mut state, &CONFIG, 150, at(0));
+        update_finger_state(&mut state, &CONFIG, 150, at(30));
+
+        assert!(update_finger_state(&mut state, &CONFIG, 80, at(40)));
+        assert!(update_finger_state(&mut state, &CONFIG, 51, at(50)));
+        assert!(!update_finger_state(&mut state, &CONFIG, 50, at(60)));
+    }
+
+    #[test]
+    fn ignores_movements_in_the_refractory_period() {
+        let mut state = FingerState::default();
+        update_finger_state(&mut state, &CONFIG, 150, at(0));
+        update_finger_state(&mut state, &CONFIG, 150, at(30));
+        update_finger_state(&mut state, &CONFIG, 0, at(40));
+
+        assert!(!update_finger_state(&mut state, &CONFIG, 150, at(60)));
+        assert!(!update_finger_state(&mut state, &CONFIG, 150, at(130)));
+        // Released 100 ms ago, the new movement starts being timed
+        assert!(!update_finger_state(&mut state, &CONFIG, 150, at(140)));
+        assert!(update_finger_state(&mut state, &CONFIG, 150, at(170)));
+    }
+}
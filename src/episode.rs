@@ -0,0 +1,126 @@
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::parser::{FingersFlexValues, MovingFingers};
+
+/// A single movement of one finger, from the first to the last sample detected as moving
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovementEpisode {
+    /// From 0 (thumb) to 4 (little finger)
+    pub finger: usize,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    /// Highest aggregated value during the movement
+    pub peak_amplitude: u32,
+    /// Sum of the aggregated values weighted by the time between samples, in value x seconds
+    pub area: f64,
+    /// Time from the start of the movement to its peak
+    pub rise_time_ms: i64,
+}
+
+impl MovementEpisode {
+    pub fn duration_ms(&self) -> i64 {
+        (self.end - self.start).num_milliseconds()
+    }
+
+    /// The `finger,start,end` columns come first so the table can be used as classifier labels
+    pub fn write_csv(episodes: &[Self], path: &Path) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_path(path)
+            .with_context(|| format!("unable to create episodes {}", path.display()))?;
+
+        for episode in episodes {
+            writer.serialize(episode)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct OpenEpisode {
+    start: DateTime<Local>,
+    last: DateTime<Local>,
+    peak_amplitude: u32,
+    peak_time: DateTime<Local>,
+    area: f64,
+}
+
+/// Turns the per-sample moving fingers into movement episodes
+#[derive(Default)]
+pub struct EpisodeExtractor {
+    open_episodes: [Option<OpenEpisode>; 5],
+}
+
+impl EpisodeExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the episodes that ended with this sample
+    pub fn push(
+        &mut self,
+        values: &FingersFlexValues,
+        moving_fingers: &MovingFingers,
+        time: DateTime<Local>,
+    ) -> Vec<MovementEpisode> {
+        let mut episodes = vec![];
+
+        for (finger, open_episode) in self.open_episodes.iter_mut().enumerate() {
+            let value = values.0[finger];
+
+            if !moving_fingers[finger] {
+                if let Some(episode) = open_episode.take() {
+                    episodes.push(close_episode(finger, episode));
+                }
+                continue;
+            }
+
+            let episode = open_episode.get_or_insert(OpenEpisode {
+                start: time,
+                last: time,
+                peak_amplitude: value,
+                peak_time: time,
+                area: 0.0,
+            });
+
+            episode.area += value as f64 * (time - episode.last).num_milliseconds() as f64 / 1000.0;
+            episode.last = time;
+
+            if value > episode.peak_amplitude {
+                episode.peak_amplitude = value;
+                episode.peak_time = time;
+            }
+        }
+
+        episodes
+    }
+
+    /// Closes the episodes still open, e.g. at the end of a recording
+    pub fn finish(&mut self) -> Vec<MovementEpisode> {
+        self.open_episodes
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(finger, open_episode)| {
+                open_episode
+                    .take()
+                    .map(|episode| close_episode(finger, episode))
+            })
+            .collect()
+    }
+}
+
+fn close_episode(finger: usize, episode: OpenEpisode) -> MovementEpisode {
+    MovementEpisode {
+        finger,
+        start: episode.start,
+        end: episode.last,
+        peak_amplitude: episode.peak_amplitude,
+        area: episode.area,
+        rise_time_ms: (episode.peak_time - episode.start).num_milliseconds(),
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    episode::MovementEpisode,
+    gate::GateState,
+    patterns::{GestureMatch, PatternProgress, ReceivedMessage, TextProgress, TypedCharacter},
+    quality::ChannelQuality,
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ProcessEvent {
+    /// Most channels deviated at once, movement detection is suppressed until it ends
+    ArtifactStarted,
+    ArtifactEnded {
+        duration_ms: i64,
+    },
+    /// No notification was received for too long to interpolate the missing samples
+    SignalGap {
+        start: DateTime<Local>,
+        duration_ms: i64,
+    },
+    ChannelQualityChanged {
+        finger: usize,
+        #[serde(flatten)]
+        quality: ChannelQuality,
+    },
+    GestureDetected(GestureMatch),
+    MovementEpisode(MovementEpisode),
+    /// Pattern decoding is suspended while the gate is active
+    GateChanged {
+        state: GateState,
+        previous_duration_ms: i64,
+    },
+    /// The arming signal was performed, text is decoded until `until`
+    DecoderArmed {
+        until: DateTime<Local>,
+    },
+    DecoderDisarmed,
+    PatternDetected {
+        name: String,
+        repetitions: u32,
+    },
+    /// Added by the `marker` action of a pattern
+    Marker {
+        label: String,
+    },
+    /// Added by the `haptic` action of a pattern, for the device or the app to play
+    HapticRequested {
+        pulses_ms: Vec<u32>,
+    },
+    ActionFailed {
+        pattern: String,
+        error: String,
+    },
+    PatternProgress(PatternProgress),
+    TextProgress(TextProgress),
+    /// Emitted by the text pattern decoding
+    CharacterTyped(TypedCharacter),
+    /// A word ended, `corrected` being the closest word of the word list
+    WordDecoded {
+        raw: String,
+        corrected: String,
+    },
+    /// Most frequent words starting with the word being typed
+    WordCompletions {
+        prefix: String,
+        completions: Vec<String>,
+    },
+    /// The start pattern of a message was performed
+    MessageStarted,
+    MessageReceived(ReceivedMessage),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimedEvent {
+    pub dt: DateTime<Local>,
+    #[serde(flatten)]
+    pub event: ProcessEvent,
+}
+
+impl TimedEvent {
+    pub fn new(dt: DateTime<Local>, event: ProcessEvent) -> Self {
+        Self { dt, event }
+    }
+}
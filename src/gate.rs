@@ -0,0 +1,173 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    events::ProcessEvent,
+    opt::FingersSensibility,
+    parser::{FingersFlexValues, MovingFingers},
+};
+
+pub const DEFAULT_GATE_MIN_CHANNELS: usize = 3;
+pub const DEFAULT_GATE_ENTER_MS: u32 = 1000;
+pub const DEFAULT_GATE_QUIET_MS: u32 = 10000;
+pub const DEFAULT_GATE_WINDOW_SIZE: usize = 50;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GateState {
+    /// Stable period, patterns are decoded
+    Quiet,
+    /// The sleeper is moving or awake, pattern decoding is suspended
+    Active,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ActivityGateConfig {
+    /// How many channels must be moving or varying at the same time for a sample to count as activity
+    pub min_channels: usize,
+    /// How long the activity must be sustained before the gate opens
+    pub enter_ms: u32,
+    /// How long without activity before decoding resumes
+    pub quiet_ms: u32,
+    /// Number of samples the variance of each channel is computed on
+    pub window_size: usize,
+}
+
+impl Default for ActivityGateConfig {
+    fn default() -> Self {
+        Self {
+            min_channels: DEFAULT_GATE_MIN_CHANNELS,
+            enter_ms: DEFAULT_GATE_ENTER_MS,
+            quiet_ms: DEFAULT_GATE_QUIET_MS,
+            window_size: DEFAULT_GATE_WINDOW_SIZE,
+        }
+    }
+}
+
+/// Classifies periods as active (awakening, gross movements) or quiet from the overall activity level.
+/// Unlike the artifact detector, it reacts to sustained activity and waits for a long calm period to close
+pub struct ActivityGate {
+    config: ActivityGateConfig,
+    /// A channel varies when its standard deviation over the window is above its sensibility
+    std_thresholds: [f32; 5],
+    windows: [VecDeque<u32>; 5],
+
+    state: GateState,
+    state_since: Option<DateTime<Local>>,
+    activity_since: Option<DateTime<Local>>,
+    last_activity_time: Option<DateTime<Local>>,
+}
+
+impl ActivityGate {
+    pub fn new(sensibility: &FingersSensibility, config: ActivityGateConfig) -> Self {
+        Self {
+            config,
+            std_thresholds: sensibility.0.map(|sensibility| sensibility as f32),
+            windows: Default::default(),
+
+            state: GateState::Quiet,
+            state_since: None,
+            activity_since: None,
+            last_activity_time: None,
+        }
+    }
+
+    pub fn set_sensibility(&mut self, sensibility: &FingersSensibility) {
+        self.std_thresholds = sensibility.0.map(|sensibility| sensibility as f32);
+    }
+
+    pub fn state(&self) -> GateState {
+        self.state
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state == GateState::Active
+    }
+
+    /// Returns an event when the gate state changes
+    pub fn process(
+        &mut self,
+        values: &FingersFlexValues,
+        moving_fingers: &MovingFingers,
+        time: DateTime<Local>,
+    ) -> Option<ProcessEvent> {
+        for (finger, window) in self.windows.iter_mut().enumerate() {
+            if window.len() >= self.config.window_size {
+                window.pop_front();
+            }
+            window.push_back(values.0[finger]);
+        }
+
+        if self.is_activity(moving_fingers) {
+            // Short pauses during a movement burst do not reset it
+            let is_new_burst = self
+                .last_activity_time
+                .is_none_or(|last| elapsed_ms(last, time) > self.config.enter_ms as i64);
+
+            if is_new_burst {
+                self.activity_since = Some(time);
+            }
+            self.last_activity_time = Some(time);
+        }
+
+        let state_since = *self.state_since.get_or_insert(time);
+
+        let next_state = match self.state {
+            GateState::Quiet => self
+                .activity_since
+                .filter(|since| elapsed_ms(*since, time) >= self.config.enter_ms as i64)
+                .map(|_| GateState::Active),
+            GateState::Active => self
+                .last_activity_time
+                .filter(|last| elapsed_ms(*last, time) >= self.config.quiet_ms as i64)
+                .map(|_| GateState::Quiet),
+        }?;
+
+        if next_state == GateState::Quiet {
+            self.activity_since = None;
+        }
+
+        self.state = next_state;
+        self.state_since = Some(time);
+
+        Some(ProcessEvent::GateChanged {
+            state: next_state,
+            previous_duration_ms: elapsed_ms(state_since, time),
+        })
+    }
+
+    fn is_activity(&self, moving_fingers: &MovingFingers) -> bool {
+        let active_channels = self
+            .windows
+            .iter()
+            .enumerate()
+            .filter(|(finger, window)| {
+                moving_fingers[*finger] || std_dev(window) > self.std_thresholds[*finger]
+            })
+            .count();
+
+        active_channels >= self.config.min_channels
+    }
+}
+
+fn std_dev(window: &VecDeque<u32>) -> f32 {
+    if window.is_empty() {
+        return 0.0;
+    }
+
+    let len = window.len() as f32;
+    let mean = window.iter().map(|v| *v as f32).sum::<f32>() / len;
+
+    (window
+        .iter()
+        .map(|v| (*v as f32 - mean).powi(2))
+        .sum::<f32>()
+        / len)
+        .sqrt()
+}
+
+fn elapsed_ms(since: DateTime<Local>, time: DateTime<Local>) -> i64 {
+    time.signed_duration_since(since).num_milliseconds()
+}
@@ -1,5 +1,13 @@
 mod aggregator;
+mod artifact;
+mod calibration;
+mod classifier;
+mod crosstalk;
+mod detection;
 mod devices;
+mod episode;
+mod events;
+mod gate;
 
 #[cfg(feature = "lsl")]
 mod lsl_setup;
@@ -9,6 +17,12 @@ mod output;
 mod parser;
 mod patterns;
 mod process;
+mod profile;
+mod quality;
+mod questions;
+mod resampler;
+mod training;
+mod transcript;
 
 pub use devices::*;
 
@@ -16,11 +30,25 @@ pub use devices::*;
 pub use lsl_setup::*;
 
 pub use aggregator::*;
+pub use artifact::*;
+pub use calibration::*;
+pub use classifier::*;
+pub use crosstalk::*;
+pub use detection::*;
+pub use episode::*;
+pub use events::*;
+pub use gate::*;
 pub use opt::*;
 pub use output::*;
 pub use parser::*;
 pub use patterns::*;
 pub use process::*;
+pub use profile::*;
+pub use quality::*;
+pub use questions::*;
+pub use resampler::*;
+pub use training::*;
+pub use transcript::*;
 
 use console::style;
 
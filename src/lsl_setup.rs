@@ -5,11 +5,13 @@ use crate::output::OutputRow;
 
 const NUMBER_OF_CHANNELS: u32 = 10;
 const MAX_BUFFERED_SECONDS: i32 = 60 * 6;
-const NOMINAL_SRATE: f64 = 50.0;
+/// Only honoured when the notifications are resampled, BLE delivers them irregularly
+pub const NOMINAL_SRATE: f64 = 50.0;
 const CHUNK_SIZE: i32 = 5;
+const IRREGULAR_RATE: f64 = 0.0;
 
-pub fn setup_stream_outlet() -> anyhow::Result<StreamOutlet> {
-    let info = setup_stream_infos()?;
+pub fn setup_stream_outlet(nominal_srate: f64) -> anyhow::Result<StreamOutlet> {
+    let info = setup_stream_infos(nominal_srate)?;
 
     Ok(lsl::StreamOutlet::new(
         &info,
@@ -18,12 +20,12 @@ pub fn setup_stream_outlet() -> anyhow::Result<StreamOutlet> {
     )?)
 }
 
-pub fn setup_stream_infos() -> anyhow::Result<StreamInfo> {
+pub fn setup_stream_infos(nominal_srate: f64) -> anyhow::Result<StreamInfo> {
     let mut info = lsl::StreamInfo::new(
         "HandData",
         "MoCap",
         NUMBER_OF_CHANNELS,
-        NOMINAL_SRATE,
+        nominal_srate,
         lsl::ChannelFormat::Int16,
         "cofield_glove",
     )?;
@@ -47,6 +49,20 @@ pub fn setup_stream_infos() -> anyhow::Result<StreamInfo> {
     Ok(info)
 }
 
+/// Irregular string stream receiving the process events as json, e.g. the arming of the decoder
+pub fn setup_marker_outlet() -> anyhow::Result<StreamOutlet> {
+    let info = lsl::StreamInfo::new(
+        "HandEvents",
+        "Markers",
+        1,
+        IRREGULAR_RATE,
+        lsl::ChannelFormat::String,
+        "cofield_glove_events",
+    )?;
+
+    Ok(lsl::StreamOutlet::new(&info, 1, MAX_BUFFERED_SECONDS)?)
+}
+
 impl ExPushable<OutputRow<'_>> for StreamOutlet {
     fn push_sample_ex(
         &self,
@@ -1,9 +1,14 @@
 use core::str;
-use std::{io, sync::Arc};
+use std::{io, path::PathBuf, sync::Arc};
 
+use anyhow::{bail, Context};
+use chrono::Local;
 use clap::Parser;
 use cofield_receiver::{
-    flex_sensor_glove::FlexSensorGlove, FlexSensorGloveNotification, Opt, Process,
+    flex_sensor_glove::FlexSensorGlove, Calibration, CalibrationEvent, ClassifierModel, Command,
+    EpisodeExtractor, FingerEvidence, FlexSensorGloveNotification, GestureTemplate, GloveProfile,
+    MovementEpisode, MovementLabel, Opt, Process, ProcessEvent, QuestionEvent, QuestionSession,
+    TextEncoder, TextPattern, DEFAULT_RESAMPLE_RATE, FINGER_NAMES,
 };
 use console::style;
 use dotenv::dotenv;
@@ -17,7 +22,7 @@ async fn main() {
     let args = Opt::parse();
 
     if let Err(error) = run(args).await {
-        eprintln!("{} {}", style("ERROR:").bold().red(), error);
+        eprintln!("{} {:#}", style("ERROR:").bold().red(), error);
         std::process::exit(1);
     }
 }
@@ -28,6 +33,151 @@ fn print_info(str: &str) {
 }
 
 async fn run(opt: Opt) -> anyhow::Result<()> {
+    match &opt.command {
+        Some(Command::Calibrate {
+            name,
+            output,
+            rest_ms,
+            flex_ms,
+        }) => {
+            GloveProfile::validate_name(name)?;
+
+            let output = output
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(format!("{name}.json")));
+
+            return run_calibration(&opt, name.clone(), output, *rest_ms, *flex_ms).await;
+        }
+        Some(Command::TrainClassifier {
+            recording,
+            labels,
+            output,
+            window_size,
+            epochs,
+        }) => {
+            let recording = FlexSensorGloveNotification::read_csv(recording)?;
+            let labels = MovementLabel::read_csv(labels)?;
+
+            let model = ClassifierModel::train(
+                &recording,
+                &labels,
+                *window_size,
+                opt.get_resampler()?,
+                opt.aggregation_size,
+                opt.get_crosstalk_compensation()?.as_ref(),
+                *epochs,
+            )?;
+            model.save(output)?;
+
+            print_info(&format!("Classifier model saved to {}", output.display()));
+            return Ok(());
+        }
+        Some(Command::ExtractEpisodes { recording, output }) => {
+            let recording = FlexSensorGloveNotification::read_csv(recording)?;
+            let episodes = extract_episodes(&opt, recording).await?;
+
+            MovementEpisode::write_csv(&episodes, output)?;
+
+            print_info(&format!(
+                "{} movement episodes saved to {}",
+                episodes.len(),
+                output.display()
+            ));
+            return Ok(());
+        }
+        Some(Command::EncodeText {
+            text,
+            output_notifications,
+        }) => {
+            let encoder = TextEncoder::new(opt.get_character_table()?);
+            let symbols = encoder.encode(text)?;
+
+            for symbol in &symbols {
+                let codes: Vec<String> = symbol
+                    .codes
+                    .iter()
+                    .map(|code| {
+                        let fingers: Vec<&str> = code
+                            .fingers
+                            .iter()
+                            .map(|finger| FINGER_NAMES[*finger])
+                            .collect();
+                        let wait = if code.waits_for_timeout() {
+                            ", wait"
+                        } else {
+                            ""
+                        };
+
+                        format!("{} ({}{wait})", code.code, fingers.join(" + "))
+                    })
+                    .collect();
+
+                println!("{:?}: {}", symbol.text, codes.join(" then "));
+            }
+
+            if let Some(output) = output_notifications {
+                let presses = encoder.presses(&symbols);
+                let rate = opt.get_resample_rate()?.unwrap_or(DEFAULT_RESAMPLE_RATE);
+                let notifications = encoder.synthetic_notifications(&presses, Local::now(), rate);
+
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_path(output)?;
+                for notification in &notifications {
+                    writer.serialize(notification)?;
+                }
+                writer.flush()?;
+
+                print_info(&format!(
+                    "{} notifications saved to {}",
+                    notifications.len(),
+                    output.display()
+                ));
+            }
+
+            return Ok(());
+        }
+        Some(Command::RecordGesture {
+            recording,
+            name,
+            finger,
+            start,
+            end,
+            min_similarity,
+            output,
+        }) => {
+            let recording = FlexSensorGloveNotification::read_csv(recording)?;
+
+            let mut template = GestureTemplate::from_recording(
+                name.clone(),
+                *finger,
+                &recording,
+                *start,
+                *end,
+                opt.get_resampler()?,
+                opt.aggregation_size,
+            )?;
+            template.min_similarity = *min_similarity;
+            template.validate()?;
+
+            let mut templates = if output.exists() {
+                GestureTemplate::load_all(output)?
+            } else {
+                vec![]
+            };
+            templates.push(template);
+            GestureTemplate::save_all(&templates, output)?;
+
+            print_info(&format!(
+                "Gesture {name} added to {} ({} templates)",
+                output.display(),
+                templates.len()
+            ));
+            return Ok(());
+        }
+        None => {}
+    }
+
     if opt.input_from_stdin {
         return run_with_stdin(opt).await;
     }
@@ -35,39 +185,221 @@ async fn run(opt: Opt) -> anyhow::Result<()> {
     let flex_sensor_glove = FlexSensorGlove::new(&opt).await?;
     let notification_stream = Box::pin(flex_sensor_glove.get_notifications_stream().await?);
 
-    let output_writer = opt.output_format.create_writer();
+    let output_writer = opt
+        .output_format
+        .create_writer(opt.output_events.as_deref())?;
 
     if opt.verbose {
         print_info("Reading notifications...");
     }
 
-    let mut process = Process::new(notification_stream, opt.fingers_sensibility).await;
+    let mut process = Process::new(notification_stream, opt.get_fingers_sensibility()?).await;
 
-    process.set_aggregator(Arc::new(Mutex::new(opt.get_mean_aggregator())));
+    configure_process(&mut process, &opt)?;
     process.set_output_writer(Arc::new(Mutex::new(Some(output_writer))));
+    process.set_transcript_writer(Arc::new(Mutex::new(opt.get_transcript_writer()?)));
+    let question_session = configure_question_session(&mut process, &opt)?;
 
     #[cfg(feature = "lsl")]
     if opt.lsl {
-        let lsl_stream_outlet = lsl_setup::setup_stream_outlet()?;
+        let nominal_srate = opt
+            .get_resample_rate()?
+            .unwrap_or(cofield_receiver::NOMINAL_SRATE);
+        let lsl_stream_outlet = cofield_receiver::setup_stream_outlet(nominal_srate)?;
         process.set_lsl_stream_outlet(lsl_stream_outlet);
+        process.set_lsl_marker_outlet(cofield_receiver::setup_marker_outlet()?);
     }
 
-    process.run().await?;
-
-    Ok(())
+    run_process(&mut process, &question_session).await
 }
 
 async fn run_with_stdin(opt: Opt) -> anyhow::Result<()> {
-    let output_writer = opt.output_format.create_writer();
+    let output_writer = opt
+        .output_format
+        .create_writer(opt.output_events.as_deref())?;
     let notification_stream = get_stdin_csv_notification_stream().await;
 
-    let mut process = Process::new(notification_stream, opt.fingers_sensibility).await;
+    let mut process = Process::new(notification_stream, opt.get_fingers_sensibility()?).await;
 
-    process.set_aggregator(Arc::new(Mutex::new(opt.get_mean_aggregator())));
+    configure_process(&mut process, &opt)?;
     process.set_output_writer(Arc::new(Mutex::new(Some(output_writer))));
+    process.set_transcript_writer(Arc::new(Mutex::new(opt.get_transcript_writer()?)));
+    let question_session = configure_question_session(&mut process, &opt)?;
+
+    run_process(&mut process, &question_session).await
+}
+
+/// Runs the process until the notifications end or Ctrl+C is pressed,
+/// then writes the answers given so far, even when the process failed
+async fn run_process(
+    process: &mut Process<'_>,
+    question_session: &Mutex<Option<QuestionSession>>,
+) -> anyhow::Result<()> {
+    let result = tokio::select! {
+        result = process.run() => result,
+        result = tokio::signal::ctrl_c() => result.map_err(anyhow::Error::from),
+    };
+
+    if let Some(question_session) = question_session.lock().await.as_mut() {
+        question_session.finish(Local::now());
+    }
+
+    result
+}
+
+/// Sets up the processing steps shared by every mode reading glove notifications
+fn configure_process(process: &mut Process, opt: &Opt) -> anyhow::Result<()> {
+    process.set_resampler(opt.get_resampler()?);
+    process.set_movement_detector(opt.get_movement_detector()?);
+    process.set_crosstalk_compensation(opt.get_crosstalk_compensation()?);
+    process.set_artifact_detector(Arc::new(Mutex::new(opt.get_artifact_detector()?)));
+    process
+        .set_signal_quality_monitor(Arc::new(Mutex::new(Some(opt.get_signal_quality_monitor()))));
+    process.set_pattern_engine(Arc::new(Mutex::new(opt.get_pattern_engine()?)));
+    process.set_decoder_arming(Arc::new(Mutex::new(opt.get_decoder_arming()?)));
+    process.set_activity_gate(Arc::new(Mutex::new(opt.get_activity_gate()?)));
+    process.set_episode_extractor(Arc::new(Mutex::new(opt.get_episode_extractor())));
+    process.set_gesture_recognizer(Arc::new(Mutex::new(opt.get_gesture_recognizer()?)));
+    process.set_aggregator(Arc::new(Mutex::new(opt.get_mean_aggregator())));
+
+    if opt.decode_text {
+        let mut text_pattern = TextPattern::new(Box::new(|str| {
+            print_info(&format!("Typed {str:?}"));
+        }));
+        text_pattern.set_character_table(opt.get_character_table()?);
+        text_pattern.set_mode(opt.get_text_mode()?);
+        text_pattern
+            .set_finger_evidence(Some(FingerEvidence::new(&opt.get_fingers_sensibility()?)));
+
+        process.set_text_pattern_detection(Arc::new(Mutex::new(Some(text_pattern))));
+        process.set_language_layer(Arc::new(Mutex::new(opt.get_language_layer()?)));
+        process.set_message_framing(Arc::new(Mutex::new(opt.get_message_framing()?)));
+    }
+
+    Ok(())
+}
+
+/// Prints the questions and their answers, which are written to `--output-answers` once the
+/// session ends
+fn configure_question_session(
+    process: &mut Process,
+    opt: &Opt,
+) -> anyhow::Result<Arc<Mutex<Option<QuestionSession>>>> {
+    let mut question_session = opt.get_question_session()?;
+
+    if let Some(question_session) = question_session.as_mut() {
+        let output_answers = opt.output_answers.clone();
+
+        question_session.on_event(move |event| match event {
+            QuestionEvent::QuestionAsked { text, options, .. } => {
+                print_info(&format!("Question: {text} ({})", options.join(", ")));
+            }
+            QuestionEvent::Answered(answer) => {
+                print_info(&format!(
+                    "Answered {:?} to {:?} with the {}",
+                    answer.answer, answer.question_id, answer.signal
+                ));
+            }
+            QuestionEvent::Finished(report) => {
+                let Some(path) = &output_answers else {
+                    return;
+                };
+
+                match report.write_csv(path) {
+                    Ok(()) => print_info(&format!(
+                        "{} answers saved to {}",
+                        report.answers.len(),
+                        path.display()
+                    )),
+                    Err(error) => eprintln!("{} {:#}", style("ERROR:").bold().red(), error),
+                }
+            }
+        });
+    }
+
+    let question_session = Arc::new(Mutex::new(question_session));
+    process.set_question_session(question_session.clone());
+
+    Ok(question_session)
+}
+
+async fn extract_episodes(
+    opt: &Opt,
+    recording: Vec<FlexSensorGloveNotification>,
+) -> anyhow::Result<Vec<MovementEpisode>> {
+    let notification_stream = futures::stream::iter(recording).boxed();
+    let mut process = Process::new(notification_stream, opt.get_fingers_sensibility()?).await;
+
+    configure_process(&mut process, opt)?;
+    process.set_episode_extractor(Arc::new(Mutex::new(Some(EpisodeExtractor::new()))));
+
+    let episodes = Arc::new(std::sync::Mutex::new(vec![]));
+    let process_episodes = episodes.clone();
+
+    process.on_event(move |event| {
+        if let ProcessEvent::MovementEpisode(episode) = &event.event {
+            process_episodes.lock().unwrap().push(episode.clone());
+        }
+    });
 
     process.run().await?;
 
+    let episodes = episodes.lock().unwrap().clone();
+    Ok(episodes)
+}
+
+async fn run_calibration(
+    opt: &Opt,
+    name: String,
+    output: PathBuf,
+    rest_ms: u32,
+    flex_ms: u32,
+) -> anyhow::Result<()> {
+    let flex_sensor_glove = FlexSensorGlove::new(opt).await?;
+    let mut notification_stream = Box::pin(flex_sensor_glove.get_notifications_stream().await?);
+
+    // Calibration values are computed on aggregated values as this is what movement detection uses
+    let mut aggregator = opt.get_mean_aggregator();
+
+    let mut calibration = Calibration::new(name, rest_ms, flex_ms);
+    calibration.on_event(|event| {
+        if let CalibrationEvent::StepStarted(step) = event {
+            print_info(&step.instruction())
+        }
+    });
+
+    while let Some(notification) = notification_stream.next().await {
+        let raw_flex_values = notification.flex_values;
+        let aggregated_notification = match aggregator.as_mut() {
+            Some(aggregator) => aggregator.push_and_aggregate(notification),
+            None => notification,
+        };
+
+        calibration.push(&raw_flex_values, &aggregated_notification);
+
+        if calibration.is_done() {
+            break;
+        }
+    }
+
+    if !calibration.is_done() {
+        bail!("the glove notifications ended before the calibration was done");
+    }
+
+    let profile = match calibration.take_profile() {
+        Some(profile) => profile,
+        None => calibration
+            .compute_profile()
+            .context("calibration failed")?,
+    };
+    profile.save(&output)?;
+
+    print_info(&format!(
+        "Calibration done, fingers sensibility {:?} saved to {}",
+        profile.fingers_sensibility.0,
+        output.display()
+    ));
+
     Ok(())
 }
 
@@ -1,9 +1,31 @@
-use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
 
-use crate::MeanAggregator;
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local};
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::{
+    check_resample_rate, ActivityGate, ActivityGateConfig, ArtifactDetector,
+    ArtifactDetectorConfig, CharacterTable, ChordDecoder, ClassifierDetector, ClassifierModel,
+    ConfusionModel, CrosstalkCompensation, DecoderArming, EpisodeExtractor, FingerEvidence,
+    GestureRecognizer, GestureTemplate, GloveProfile, HysteresisDetector, LanguageLayer,
+    MeanAggregator, MessageFraming, MessageFramingConfig, MorseConfig, MorseDecoder,
+    MovementDetectorDyn, PatternConfig, PatternDefinition, PatternEngine, PatternSpec,
+    QuestionList, QuestionSession, Resampler, SignalQualityConfig, SignalQualityMonitor, TextMode,
+    TrainingHistory, TranscriptWriter, WordList, DEFAULT_ARMING_REPETITIONS,
+    DEFAULT_ARMING_WINDOW_MS, DEFAULT_ARTIFACT_DEVIATION_FACTOR, DEFAULT_ARTIFACT_HOLD_OFF_MS,
+    DEFAULT_ARTIFACT_MIN_CHANNELS, DEFAULT_CALIBRATION_FLEX_MS, DEFAULT_CALIBRATION_REST_MS,
+    DEFAULT_CHORD_SETTLE_MS, DEFAULT_CLASSIFIER_EPOCHS, DEFAULT_CLASSIFIER_WINDOW_SIZE,
+    DEFAULT_GATE_ENTER_MS, DEFAULT_GATE_MIN_CHANNELS, DEFAULT_GATE_QUIET_MS,
+    DEFAULT_GESTURE_MIN_SIMILARITY, DEFAULT_MAX_GAP_MS, DEFAULT_MIN_ON_MS, DEFAULT_REFRACTORY_MS,
+    DEFAULT_RELEASE_RATIO,
+};
 
 #[derive(Parser)]
 pub struct Opt {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Read from stdin to get the glove notifications in csv format.
     /// In this mode, the gloves are not used and lsl is disabled
     /// This is useful to process previously recorded data with different settings
@@ -13,6 +35,10 @@ pub struct Opt {
     #[arg(short, long, value_enum, default_value_t=OutputFormat::default())]
     pub output_format: OutputFormat,
 
+    /// Write the events to this csv file, with their time and their json
+    #[arg(long)]
+    pub output_events: Option<PathBuf>,
+
     #[arg(long, default_value = "FlexSensorGlove")]
     pub output_glove_name: String,
 
@@ -22,6 +48,159 @@ pub struct Opt {
     #[arg(long, default_value = "[15, 15, 15, 15, 15]")]
     pub fingers_sensibility: FingersSensibility,
 
+    /// Glove profile produced by the `calibrate` command.
+    /// When given, its fingers sensibility replaces `--fingers-sensibility`
+    #[arg(long)]
+    pub profile: Option<PathBuf>,
+
+    /// Interpolate the notifications onto a uniform time grid at this rate (Hz)
+    #[arg(long)]
+    pub resample_rate: Option<f64>,
+
+    /// Longer gaps between notifications are not interpolated but reported as events
+    #[arg(long, default_value_t = DEFAULT_MAX_GAP_MS)]
+    pub max_gap_ms: u32,
+
+    /// Model trained with the `train-classifier` command, replaces threshold detection
+    #[arg(long)]
+    pub classifier_model: Option<PathBuf>,
+
+    /// A moving finger is released when its value goes below `sensibility * release_ratio`
+    #[arg(long, default_value_t = DEFAULT_RELEASE_RATIO)]
+    pub release_ratio: f32,
+
+    /// How long a finger must stay above its sensibility to be considered moving
+    #[arg(long, default_value_t = DEFAULT_MIN_ON_MS)]
+    pub min_on_ms: u32,
+
+    /// How long after a release a new movement of the same finger is ignored
+    #[arg(long, default_value_t = DEFAULT_REFRACTORY_MS)]
+    pub refractory_ms: u32,
+
+    /// Suppress movement detection while most channels deviate at once (e.g. the sleeper turns over)
+    #[arg(long, default_value = "false")]
+    pub reject_artifacts: bool,
+
+    /// A channel deviates when its value is above `sensibility * artifact_deviation_factor`
+    #[arg(long, default_value_t = DEFAULT_ARTIFACT_DEVIATION_FACTOR)]
+    pub artifact_deviation_factor: f32,
+
+    /// How many channels must deviate at the same time to detect an artifact
+    #[arg(long, default_value_t = DEFAULT_ARTIFACT_MIN_CHANNELS)]
+    pub artifact_min_channels: usize,
+
+    /// How long detection stays suppressed after the last deviation
+    #[arg(long, default_value_t = DEFAULT_ARTIFACT_HOLD_OFF_MS)]
+    pub artifact_hold_off_ms: u32,
+
+    /// Never report movements of fingers whose sensor looks broken (flat, saturated, noisy...)
+    #[arg(long, default_value = "false")]
+    pub mask_bad_channels: bool,
+
+    /// Suspend pattern decoding during sustained activity, like awakenings, until a quiet period
+    #[arg(long, default_value = "false")]
+    pub activity_gate: bool,
+
+    /// How many channels must be moving or varying at once to count as activity
+    #[arg(long, default_value_t = DEFAULT_GATE_MIN_CHANNELS)]
+    pub gate_min_channels: usize,
+
+    /// How long the activity must be sustained to suspend decoding
+    #[arg(long, default_value_t = DEFAULT_GATE_ENTER_MS)]
+    pub gate_enter_ms: u32,
+
+    /// How long without activity before decoding resumes
+    #[arg(long, default_value_t = DEFAULT_GATE_QUIET_MS)]
+    pub gate_quiet_ms: u32,
+
+    /// Fingers (0 to 4, comma separated) of the signal that must be performed before text is decoded.
+    /// Without it, text is always decoded
+    #[arg(long, value_delimiter = ',')]
+    pub arming_pattern: Option<Vec<u8>>,
+
+    /// How many times in a row the arming signal must be performed
+    #[arg(long, default_value_t = DEFAULT_ARMING_REPETITIONS)]
+    pub arming_repetitions: u32,
+
+    /// How long text is decoded after the arming signal
+    #[arg(long, default_value_t = DEFAULT_ARMING_WINDOW_MS)]
+    pub arming_window_ms: u32,
+
+    /// Named finger sequence reported as an event when performed, e.g. `--pattern marker=0,4x2`
+    /// for thumb then little finger twice in a row. Can be given several times
+    #[arg(long = "pattern")]
+    pub patterns: Vec<PatternSpec>,
+
+    /// Toml or json file describing patterns (sequences, chords, delays) and their actions
+    #[arg(long)]
+    pub pattern_config: Option<PathBuf>,
+
+    /// Let the pattern config start programs with `command` actions
+    #[arg(long, default_value = "false")]
+    pub allow_commands: bool,
+
+    /// Decode the finger movements into text, printed as they are typed
+    #[arg(long, default_value = "false")]
+    pub decode_text: bool,
+
+    /// Built in character table (en, fr, de) or toml/json file mapping the codes to symbols
+    #[arg(long, default_value = "en")]
+    pub character_table: String,
+
+    /// How the finger movements are decoded into text
+    #[arg(long, value_enum, default_value_t=TextModeKind::default())]
+    pub text_mode: TextModeKind,
+
+    /// Finger read in Morse mode, from 0 (thumb) to 4 (little finger), any finger by default
+    #[arg(long)]
+    pub morse_finger: Option<usize>,
+
+    /// Initial duration of a Morse dot, adapted to the speed of the participant
+    #[arg(long, default_value_t = MorseConfig::default().unit_ms)]
+    pub morse_unit_ms: u32,
+
+    /// How long after the first finger of a chord the other fingers can join it, in chord mode
+    #[arg(long, default_value_t = DEFAULT_CHORD_SETTLE_MS)]
+    pub chord_settle_ms: u32,
+
+    /// Built in word list (en, fr, de) or text file with one word per line, used to complete
+    /// and correct the decoded words
+    #[arg(long)]
+    pub word_list: Option<String>,
+
+    /// Training history of the participant, the letters they confused are corrected first
+    #[arg(long)]
+    pub training_history: Option<PathBuf>,
+
+    /// Pattern starting a message typed with `--decode-text`, the message ends with a check
+    /// symbol followed by `--message-end-pattern`
+    #[arg(long, requires = "message_end_pattern")]
+    pub message_start_pattern: Option<String>,
+
+    /// Pattern ending a message, it can be the start one
+    #[arg(long, requires = "message_start_pattern")]
+    pub message_end_pattern: Option<String>,
+
+    /// Toml or json file of the questions to answer with fingers or patterns, in order
+    #[arg(long)]
+    pub questions: Option<PathBuf>,
+
+    /// Name of the participant written with the answers
+    #[arg(long, default_value = "")]
+    pub participant: String,
+
+    /// Csv file the answers to `--questions` are written to at the end of the session
+    #[arg(long, requires = "questions")]
+    pub output_answers: Option<PathBuf>,
+
+    /// Report each movement as an episode event with its duration and amplitude
+    #[arg(long, default_value = "false")]
+    pub movement_episodes: bool,
+
+    /// Gesture templates recorded with the `record-gesture` command, reported as events when matched
+    #[arg(long)]
+    pub gestures: Option<PathBuf>,
+
     #[arg(long, short, default_value = "false")]
     pub verbose: bool,
 
@@ -31,6 +210,99 @@ pub struct Opt {
 
     #[arg(long)]
     pub output_raw_data: Option<std::path::PathBuf>,
+
+    /// Write the decoded characters and events to this file as JSON Lines,
+    /// and to the same file with the `txt` extension as plain text
+    #[arg(long)]
+    pub output_transcript: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Record a rest period and guided flexes of each finger to create a glove profile
+    Calibrate {
+        /// Name of the participant or glove the profile is made for
+        #[arg(long)]
+        name: String,
+
+        /// Where to save the profile, defaults to `<name>.json` in the current folder
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        #[arg(long, default_value_t = DEFAULT_CALIBRATION_REST_MS)]
+        rest_ms: u32,
+
+        #[arg(long, default_value_t = DEFAULT_CALIBRATION_FLEX_MS)]
+        flex_ms: u32,
+    },
+    /// Train a movement classifier from a raw recording and its labelled movements.
+    /// The recording is aggregated with `--aggregation-size`
+    TrainClassifier {
+        /// Raw recording made with `--output-raw-data` or the app
+        #[arg(long)]
+        recording: PathBuf,
+
+        /// Csv file with a `finger,start,end` header, fingers going from 0 (thumb) to 4
+        #[arg(long)]
+        labels: PathBuf,
+
+        #[arg(long)]
+        output: PathBuf,
+
+        #[arg(long, default_value_t = DEFAULT_CLASSIFIER_WINDOW_SIZE)]
+        window_size: usize,
+
+        #[arg(long, default_value_t = DEFAULT_CLASSIFIER_EPOCHS)]
+        epochs: usize,
+    },
+    /// Write the movement episodes of a raw recording to a csv table, processed with the same options
+    ExtractEpisodes {
+        /// Raw recording made with `--output-raw-data` or the app
+        #[arg(long)]
+        recording: PathBuf,
+
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Print the finger sequence typing a text with `--character-table`
+    EncodeText {
+        #[arg(long)]
+        text: String,
+
+        /// Also write the raw notifications of a glove typing the text, to replay with `--input-from-stdin`
+        #[arg(long)]
+        output_notifications: Option<PathBuf>,
+    },
+    /// Add a gesture template extracted from an example in a raw recording.
+    /// The recording is aggregated with `--aggregation-size`
+    RecordGesture {
+        /// Raw recording made with `--output-raw-data` or the app
+        #[arg(long)]
+        recording: PathBuf,
+
+        #[arg(long)]
+        name: String,
+
+        /// From 0 (thumb) to 4 (little finger)
+        #[arg(long)]
+        finger: usize,
+
+        /// Start of the example, in rfc3339 format
+        #[arg(long)]
+        start: DateTime<Local>,
+
+        /// End of the example, in rfc3339 format
+        #[arg(long)]
+        end: DateTime<Local>,
+
+        /// Similarity from 0 to 1 above which the gesture is detected
+        #[arg(long, default_value_t = DEFAULT_GESTURE_MIN_SIMILARITY)]
+        min_similarity: f32,
+
+        /// Gestures file the template is added to, created if it does not exist
+        #[arg(long)]
+        output: PathBuf,
+    },
 }
 
 impl Default for Opt {
@@ -39,7 +311,7 @@ impl Default for Opt {
     }
 }
 
-#[derive(Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub struct FingersSensibility(pub [u32; 5]);
 
 impl std::str::FromStr for FingersSensibility {
@@ -50,6 +322,17 @@ impl std::str::FromStr for FingersSensibility {
     }
 }
 
+#[derive(Copy, Clone, Default, ValueEnum)]
+pub enum TextModeKind {
+    /// Two successive hand positions give a character
+    #[default]
+    Positions,
+    /// Short and long flexes of a finger
+    Morse,
+    /// The fingers flexed together, read as a 5-bit code
+    Chord,
+}
+
 #[derive(Copy, Clone, Default, ValueEnum)]
 pub enum OutputFormat {
     #[default]
@@ -65,4 +348,258 @@ impl Opt {
             None
         }
     }
+
+    pub fn get_profile(&self) -> anyhow::Result<Option<GloveProfile>> {
+        self.profile.as_deref().map(GloveProfile::load).transpose()
+    }
+
+    pub fn get_fingers_sensibility(&self) -> anyhow::Result<FingersSensibility> {
+        Ok(self
+            .get_profile()?
+            .map(|profile| profile.fingers_sensibility)
+            .unwrap_or(self.fingers_sensibility))
+    }
+
+    /// Crosstalk compensation is only available when the profile contains a coupling matrix
+    pub fn get_crosstalk_compensation(&self) -> anyhow::Result<Option<CrosstalkCompensation>> {
+        match self.get_profile()? {
+            Some(profile) => profile.crosstalk_compensation(),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_artifact_detector_config(&self) -> ArtifactDetectorConfig {
+        ArtifactDetectorConfig {
+            deviation_factor: self.artifact_deviation_factor,
+            min_channels: self.artifact_min_channels,
+            hold_off_ms: self.artifact_hold_off_ms,
+        }
+    }
+
+    pub fn get_artifact_detector(&self) -> anyhow::Result<Option<ArtifactDetector>> {
+        if !self.reject_artifacts {
+            return Ok(None);
+        }
+
+        Ok(Some(ArtifactDetector::new(
+            &self.get_fingers_sensibility()?,
+            self.get_artifact_detector_config(),
+        )))
+    }
+
+    pub fn get_activity_gate(&self) -> anyhow::Result<Option<ActivityGate>> {
+        if !self.activity_gate {
+            return Ok(None);
+        }
+
+        let config = ActivityGateConfig {
+            min_channels: self.gate_min_channels,
+            enter_ms: self.gate_enter_ms,
+            quiet_ms: self.gate_quiet_ms,
+            ..Default::default()
+        };
+
+        Ok(Some(ActivityGate::new(
+            &self.get_fingers_sensibility()?,
+            config,
+        )))
+    }
+
+    pub fn get_decoder_arming(&self) -> anyhow::Result<Option<DecoderArming>> {
+        self.arming_pattern
+            .clone()
+            .map(|fingers_order| {
+                DecoderArming::new(
+                    fingers_order,
+                    self.arming_repetitions,
+                    self.arming_window_ms,
+                )
+            })
+            .transpose()
+    }
+
+    pub fn get_pattern_definitions(&self) -> anyhow::Result<Vec<PatternDefinition>> {
+        let mut definitions: Vec<PatternDefinition> =
+            self.patterns.iter().map(PatternDefinition::from).collect();
+
+        if let Some(path) = &self.pattern_config {
+            let config = PatternConfig::load(path)?;
+            for definition in &config.patterns {
+                definition
+                    .check_commands_allowed(self.allow_commands)
+                    .with_context(|| {
+                        format!(
+                            "invalid pattern config {} without --allow-commands",
+                            path.display()
+                        )
+                    })?;
+            }
+
+            definitions.extend(config.patterns);
+        }
+
+        Ok(definitions)
+    }
+
+    pub fn get_pattern_engine(&self) -> anyhow::Result<Option<PatternEngine>> {
+        let definitions = self.get_pattern_definitions()?;
+
+        if definitions.is_empty() {
+            return Ok(None);
+        }
+
+        PatternEngine::from_definitions(&definitions).map(Some)
+    }
+
+    pub fn get_question_session(&self) -> anyhow::Result<Option<QuestionSession>> {
+        let Some(path) = &self.questions else {
+            return Ok(None);
+        };
+
+        let question_list = QuestionList::load(path)?;
+        question_list.validate(&self.get_pattern_definitions()?)?;
+
+        let mut question_session = QuestionSession::new(self.participant.clone(), &question_list)?;
+        question_session
+            .set_finger_evidence(Some(FingerEvidence::new(&self.get_fingers_sensibility()?)));
+
+        Ok(Some(question_session))
+    }
+
+    pub fn get_transcript_writer(&self) -> anyhow::Result<Option<TranscriptWriter>> {
+        self.output_transcript
+            .as_deref()
+            .map(TranscriptWriter::create)
+            .transpose()
+    }
+
+    pub fn get_message_framing(&self) -> anyhow::Result<Option<MessageFraming>> {
+        let (Some(start_pattern), Some(end_pattern)) =
+            (&self.message_start_pattern, &self.message_end_pattern)
+        else {
+            return Ok(None);
+        };
+
+        let config = MessageFramingConfig::new(start_pattern.clone(), end_pattern.clone());
+        let definitions = self.get_pattern_definitions()?;
+        let pattern_names: Vec<&str> = definitions
+            .iter()
+            .map(|definition| definition.name.as_str())
+            .collect();
+        config.validate(&pattern_names)?;
+
+        Ok(Some(MessageFraming::new(
+            config,
+            self.get_character_table()?,
+        )))
+    }
+
+    pub fn get_text_mode(&self) -> anyhow::Result<TextMode> {
+        Ok(match self.text_mode {
+            TextModeKind::Positions => TextMode::Positions,
+            TextModeKind::Morse => {
+                let config = MorseConfig {
+                    finger: self.morse_finger,
+                    unit_ms: self.morse_unit_ms,
+                    ..Default::default()
+                };
+                config.validate()?;
+
+                TextMode::Morse(MorseDecoder::new(config))
+            }
+            TextModeKind::Chord => TextMode::Chord(ChordDecoder::new(self.chord_settle_ms)),
+        })
+    }
+
+    pub fn get_language_layer(&self) -> anyhow::Result<Option<LanguageLayer>> {
+        let Some(word_list) = &self.word_list else {
+            return Ok(None);
+        };
+
+        let word_list = WordList::load_or_builtin(word_list)?;
+        let mut confusion_model =
+            ConfusionModel::new(&self.get_character_table()?, &self.get_text_mode()?);
+
+        if let Some(path) = &self.training_history {
+            let history = TrainingHistory::load(path)?;
+            confusion_model.add_training_history(&history);
+        }
+
+        Ok(Some(LanguageLayer::new(word_list, confusion_model)))
+    }
+
+    pub fn get_character_table(&self) -> anyhow::Result<CharacterTable> {
+        CharacterTable::load_or_builtin(&self.character_table)
+    }
+
+    pub fn get_resample_rate(&self) -> anyhow::Result<Option<f64>> {
+        if let Some(rate) = self
+            .resample_rate
+            .filter(|rate| !rate.is_finite() || *rate <= 0.0)
+        {
+            bail!("invalid resample rate {rate}, expected a positive rate");
+        }
+
+        Ok(self.resample_rate)
+    }
+
+    pub fn get_resampler(&self) -> anyhow::Result<Option<Resampler>> {
+        Ok(self
+            .get_resample_rate()?
+            .map(|rate| Resampler::new(rate, self.max_gap_ms)))
+    }
+
+    pub fn get_signal_quality_monitor(&self) -> SignalQualityMonitor {
+        SignalQualityMonitor::new(SignalQualityConfig {
+            mask_bad_channels: self.mask_bad_channels,
+            ..Default::default()
+        })
+    }
+
+    pub fn get_episode_extractor(&self) -> Option<EpisodeExtractor> {
+        self.movement_episodes.then(EpisodeExtractor::new)
+    }
+
+    pub fn get_gesture_recognizer(&self) -> anyhow::Result<Option<GestureRecognizer>> {
+        let Some(path) = self.gestures.as_deref() else {
+            return Ok(None);
+        };
+
+        let templates = GestureTemplate::load_all(path)?;
+        let resample_rate = self.get_resample_rate()?;
+        for template in &templates {
+            template.check_resample_rate(resample_rate)?;
+        }
+
+        Ok(Some(GestureRecognizer::new(templates)))
+    }
+
+    pub fn get_movement_detector(&self) -> anyhow::Result<MovementDetectorDyn> {
+        if let Some(path) = &self.classifier_model {
+            let model = ClassifierModel::load(path)?;
+
+            if model.aggregation_size != self.aggregation_size {
+                bail!(
+                    "the classifier model was trained with an aggregation size of {}, not {}",
+                    model.aggregation_size,
+                    self.aggregation_size
+                );
+            }
+
+            check_resample_rate(
+                "classifier model",
+                model.resample_rate,
+                self.get_resample_rate()?,
+            )?;
+
+            return Ok(Box::new(ClassifierDetector::new(model)));
+        }
+
+        Ok(Box::new(HysteresisDetector::from_sensibility(
+            &self.get_fingers_sensibility()?,
+            self.release_ratio,
+            self.min_on_ms,
+            self.refractory_ms,
+        )))
+    }
 }
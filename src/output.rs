@@ -1,11 +1,14 @@
 use std::{
     fmt::{self, Display, Formatter},
+    fs::File,
     io::Stdout,
+    path::Path,
 };
 
+use anyhow::Context;
 use serde::Serialize;
 
-use crate::{opt::OutputFormat, parser::FlexSensorGloveNotification};
+use crate::{events::TimedEvent, opt::OutputFormat, parser::FlexSensorGloveNotification};
 
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -26,35 +29,93 @@ impl Display for OutputRow<'_> {
 
 pub trait OutputWriter {
     fn write_row(&mut self, record: &OutputRow) -> anyhow::Result<()>;
+    fn write_event(&mut self, event: &TimedEvent) -> anyhow::Result<()>;
 }
 
 pub type OutputWriterDyn = Box<dyn OutputWriter + Send>;
 
-struct PrettyWriter;
+/// Events are kept out of the data rows, in their own `dt,event` csv file
+pub struct EventsWriter {
+    writer: csv::Writer<File>,
+}
+
+impl EventsWriter {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let mut writer = csv::Writer::from_path(path)
+            .with_context(|| format!("unable to create events {}", path.display()))?;
+        writer.write_record(["dt", "event"])?;
+
+        Ok(Self { writer })
+    }
+
+    pub fn write_event(&mut self, event: &TimedEvent) -> anyhow::Result<()> {
+        self.writer
+            .write_record([event.dt.to_rfc3339(), serde_json::to_string(&event.event)?])?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+struct PrettyWriter {
+    events_writer: Option<EventsWriter>,
+}
 
 impl OutputWriter for PrettyWriter {
     fn write_row(&mut self, record: &OutputRow) -> anyhow::Result<()> {
         println!("{record}");
         Ok(())
     }
+
+    fn write_event(&mut self, event: &TimedEvent) -> anyhow::Result<()> {
+        println!(
+            "{}: event: {}",
+            event.dt,
+            serde_json::to_string(&event.event)?
+        );
+
+        if let Some(events_writer) = self.events_writer.as_mut() {
+            events_writer.write_event(event)?;
+        }
+        Ok(())
+    }
 }
 
-impl OutputWriter for csv::Writer<Stdout> {
+/// Only the data rows go to stdout, so that every row has the same columns
+struct CsvWriter {
+    writer: csv::Writer<Stdout>,
+    events_writer: Option<EventsWriter>,
+}
+
+impl OutputWriter for CsvWriter {
     fn write_row(&mut self, record: &OutputRow) -> anyhow::Result<()> {
-        self.serialize(record)?;
+        self.writer.serialize(record)?;
+        Ok(())
+    }
+
+    fn write_event(&mut self, event: &TimedEvent) -> anyhow::Result<()> {
+        if let Some(events_writer) = self.events_writer.as_mut() {
+            events_writer.write_event(event)?;
+        }
         Ok(())
     }
 }
 
 impl OutputFormat {
-    pub fn create_writer(&self) -> Box<dyn OutputWriter + Send> {
-        match self {
-            OutputFormat::Pretty => Box::new(PrettyWriter),
-            OutputFormat::Csv => Box::new(
-                csv::WriterBuilder::new()
+    /// Events are also written to `events_path` when given
+    pub fn create_writer(
+        &self,
+        events_path: Option<&Path>,
+    ) -> anyhow::Result<Box<dyn OutputWriter + Send>> {
+        let events_writer = events_path.map(EventsWriter::create).transpose()?;
+
+        Ok(match self {
+            OutputFormat::Pretty => Box::new(PrettyWriter { events_writer }),
+            OutputFormat::Csv => Box::new(CsvWriter {
+                writer: csv::WriterBuilder::new()
                     .has_headers(false)
                     .from_writer(std::io::stdout()),
-            ),
-        }
+                events_writer,
+            }),
+        })
     }
 }
@@ -2,30 +2,18 @@ use std::{
     fmt::{self, Display, Formatter},
     iter::Sum,
     ops::{Add, Div, Sub},
+    path::Path,
 };
 
+use anyhow::Context;
 use chrono::{DateTime, Local, TimeDelta};
 use serde::{Deserialize, Serialize};
 
-use crate::opt::FingersSensibility;
-
 pub type MovingFingers = [bool; 5];
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct FingersFlexValues(pub [u32; 5]);
 
-impl FingersFlexValues {
-    pub fn detect_moved_fingers(&self, sensibility: &FingersSensibility) -> MovingFingers {
-        let mut moved_fingers = [false; 5];
-
-        self.0.iter().enumerate().for_each(|(i, &value)| {
-            moved_fingers[i] = value > sensibility.0[i];
-        });
-
-        moved_fingers
-    }
-}
-
 impl Div<u32> for FingersFlexValues {
     type Output = Self;
 
@@ -80,6 +68,17 @@ pub struct FlexSensorGloveNotification {
 }
 
 impl FlexSensorGloveNotification {
+    /// Reads notifications recorded with `--output-raw-data` or by the app
+    pub fn read_csv(path: &Path) -> anyhow::Result<Vec<Self>> {
+        csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_path(path)
+            .with_context(|| format!("unable to open recording {}", path.display()))?
+            .into_deserialize()
+            .collect::<Result<Vec<Self>, _>>()
+            .with_context(|| format!("invalid recording {}", path.display()))
+    }
+
     pub fn from_buffer(buffer: &[u8], dt_start: DateTime<Local>) -> Self {
         let mut flex_values = [0; 5];
         for i in 0..5 {
@@ -0,0 +1,140 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use anyhow::{bail, Context};
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::{Deserialize, Serialize};
+
+use crate::ProcessEvent;
+
+/// What happens when a pattern is detected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PatternAction {
+    /// Adds a labelled event to the outputs
+    Marker { label: String },
+    /// Types the text with keyboard emulation
+    Text { text: String },
+    /// Presses a key, e.g. `enter`, `space`, `backspace` or a single character
+    Key { key: String },
+    /// Starts a program without waiting for it to finish
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Asks for a vibration sequence, alternating on and off durations
+    Haptic { pulses_ms: Vec<u32> },
+}
+
+impl PatternAction {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Marker { label } if label.is_empty() => bail!("the marker label is empty"),
+            Self::Text { text } if text.is_empty() => bail!("the text is empty"),
+            Self::Key { key } => key_from_name(key).map(|_| ()),
+            Self::Command { program, .. } if program.is_empty() => bail!("the program is empty"),
+            Self::Haptic { pulses_ms } if pulses_ms.is_empty() => {
+                bail!("the haptic pulses are empty")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Performs the keyboard and command actions, the others are reported as events by `Process`
+    pub fn run(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Text { text } => {
+                let mut enigo = Enigo::new(&Settings::default())?;
+                enigo.text(text)?;
+            }
+            Self::Key { key } => {
+                let mut enigo = Enigo::new(&Settings::default())?;
+                enigo.key(key_from_name(key)?, Direction::Click)?;
+            }
+            Self::Command { program, args } => {
+                std::process::Command::new(program)
+                    .args(args)
+                    .spawn()
+                    .with_context(|| format!("unable to start {program}"))?;
+            }
+            Self::Marker { .. } | Self::Haptic { .. } => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs the keyboard and command actions in order on a dedicated thread,
+/// so that they do not hold up the processing of the notifications
+pub struct ActionRunner {
+    actions: Sender<(String, PatternAction)>,
+    failures: Receiver<ProcessEvent>,
+}
+
+impl ActionRunner {
+    pub fn new() -> Self {
+        let (actions, pending_actions) = mpsc::channel::<(String, PatternAction)>();
+        let (failed_actions, failures) = mpsc::channel();
+
+        // The thread ends once the runner is dropped
+        std::thread::spawn(move || {
+            for (pattern, action) in pending_actions {
+                if let Err(err) = action.run() {
+                    let event = ProcessEvent::ActionFailed {
+                        pattern,
+                        error: format!("{err:#}"),
+                    };
+                    if failed_actions.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self { actions, failures }
+    }
+
+    pub fn run(&self, pattern: &str, action: PatternAction) {
+        // The thread only stops when the runner is dropped
+        let _ = self.actions.send((pattern.to_string(), action));
+    }
+
+    /// Returns the `ActionFailed` events of the actions that failed since the last call
+    pub fn take_failures(&self) -> Vec<ProcessEvent> {
+        self.failures.try_iter().collect()
+    }
+}
+
+impl Default for ActionRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn key_from_name(name: &str) -> anyhow::Result<Key> {
+    let key = match name.to_lowercase().as_str() {
+        "enter" | "return" => Key::Return,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "tab" => Key::Tab,
+        "escape" => Key::Escape,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(char), None) => Key::Unicode(char),
+                _ => bail!("unknown key {name}"),
+            }
+        }
+    };
+
+    Ok(key)
+}
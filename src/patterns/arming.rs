@@ -0,0 +1,75 @@
+use anyhow::bail;
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::events::ProcessEvent;
+
+use super::{
+    Pattern, ReapeatingPattern, DEFAULT_PATTERN_MAX_DELAY, DEFAULT_REPEATING_PATTERN_DELAY,
+};
+
+pub const DEFAULT_ARMING_REPETITIONS: u32 = 2;
+pub const DEFAULT_ARMING_WINDOW_MS: u32 = 60_000;
+
+/// Enables text decoding for a time window once the sleeper performs the agreed signal,
+/// e.g. to show they are lucid. Movements outside of the window are not decoded
+pub struct DecoderArming {
+    signal: ReapeatingPattern,
+    repetitions: u32,
+    window_ms: u32,
+
+    armed_until: Option<DateTime<Local>>,
+}
+
+impl DecoderArming {
+    pub fn new(fingers_order: Vec<u8>, repetitions: u32, window_ms: u32) -> anyhow::Result<Self> {
+        if fingers_order.is_empty() {
+            bail!("the arming signal must contain at least one finger");
+        }
+
+        if let Some(finger) = fingers_order.iter().find(|finger| **finger > 4) {
+            bail!("invalid finger {finger} in the arming signal, expected 0 to 4");
+        }
+
+        Ok(Self {
+            signal: ReapeatingPattern::new(
+                Pattern::new(fingers_order, DEFAULT_PATTERN_MAX_DELAY)?,
+                DEFAULT_REPEATING_PATTERN_DELAY,
+            ),
+            repetitions: repetitions.max(1),
+            window_ms,
+
+            armed_until: None,
+        })
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed_until.is_some()
+    }
+
+    /// Returns an event when the decoder is armed, re-armed or disarmed
+    pub fn process(
+        &mut self,
+        moved_fingers: &[bool; 5],
+        time: DateTime<Local>,
+    ) -> Option<ProcessEvent> {
+        self.signal.process_moved_fingers(moved_fingers, time);
+
+        if self.signal.nb_done >= self.repetitions {
+            self.signal.nb_done = 0;
+
+            // Performing the signal again while armed extends the window
+            let until = time + TimeDelta::milliseconds(self.window_ms as i64);
+            self.armed_until = Some(until);
+
+            return Some(ProcessEvent::DecoderArmed { until });
+        }
+
+        match self.armed_until {
+            Some(until) if time >= until => {
+                self.armed_until = None;
+                Some(ProcessEvent::DecoderDisarmed)
+            }
+            _ => None,
+        }
+    }
+}
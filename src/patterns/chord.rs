@@ -0,0 +1,152 @@
+use chrono::{DateTime, Local};
+
+/// Chord of the five fingers, it is not in the character tables and goes back to the default layer
+pub const FULL_HAND_CHORD_CODE: u8 = 0b11111;
+
+/// Reads the set of fingers flexed together as a 5-bit code, the thumb being the lowest bit.
+/// Fingers joining the chord during the settle window are part of it
+pub struct ChordDecoder {
+    settle_ms: u32,
+
+    chord: [bool; 5],
+    started_at: Option<DateTime<Local>>,
+    /// A new chord starts once every finger of the committed one is released
+    waiting_release: bool,
+}
+
+impl ChordDecoder {
+    pub fn new(settle_ms: u32) -> Self {
+        Self {
+            settle_ms,
+
+            chord: [false; 5],
+            started_at: None,
+            waiting_release: false,
+        }
+    }
+
+    pub fn settle_ms(&self) -> u32 {
+        self.settle_ms
+    }
+
+    /// Code of the fingers flexed so far in the chord being typed
+    pub fn pending_code(&self) -> Option<u8> {
+        self.started_at.map(|_| chord_code(&self.chord))
+    }
+
+    /// Time left before the chord being typed is committed
+    pub fn remaining_ms(&self, time: DateTime<Local>) -> Option<i64> {
+        let elapsed_ms = time
+            .signed_duration_since(self.started_at?)
+            .num_milliseconds();
+
+        Some((self.settle_ms as i64 - elapsed_ms).max(0))
+    }
+
+    pub fn reset(&mut self) {
+        self.chord = [false; 5];
+        self.started_at = None;
+        self.waiting_release = false;
+    }
+
+    /// Returns the code of the chord once it settled
+    pub fn process(&mut self, moved_fingers: &[bool; 5], time: DateTime<Local>) -> Option<u8> {
+        if self.waiting_release {
+            self.waiting_release = moved_fingers.contains(&true);
+            return None;
+        }
+
+        let started_at = match self.started_at {
+            Some(started_at) => started_at,
+            None if moved_fingers.contains(&true) => {
+                self.started_at = Some(time);
+                time
+            }
+            None => return None,
+        };
+
+        for (in_chord, moved) in self.chord.iter_mut().zip(moved_fingers) {
+            *in_chord |= *moved;
+        }
+
+        let elapsed_ms = time.signed_duration_since(started_at).num_milliseconds();
+        if elapsed_ms < self.settle_ms as i64 {
+            return None;
+        }
+
+        let code = chord_code(&self.chord);
+        self.reset();
+        self.waiting_release = moved_fingers.contains(&true);
+
+        Some(code)
+    }
+}
+
+pub fn chord_code(fingers: &[bool; 5]) -> u8 {
+    fingers
+        .iter()
+        .enumerate()
+        .filter(|(_, is_flexed)| **is_flexed)
+        .map(|(finger, _)| 1 << finger)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const NONE: [bool; 5] = [false; 5];
+    const INDEX: [bool; 5] = [false, true, false, false, false];
+    const INDEX_MIDDLE: [bool; 5] = [false, true, true, false, false];
+
+    fn at(ms: i64) -> DateTime<Local> {
+        Local.timestamp_millis_opt(1_000_000 + ms).unwrap()
+    }
+
+    #[test]
+    fn thumb_is_the_lowest_bit() {
+        assert_eq!(chord_code(&[true, false, false, false, false]), 0b00001);
+        assert_eq!(chord_code(&INDEX_MIDDLE), 0b00110);
+        assert_eq!(chord_code(&[true; 5]), FULL_HAND_CHORD_CODE);
+    }
+
+    #[test]
+    fn fingers_joining_during_the_settle_window_are_in_the_chord() {
+        let mut decoder = ChordDecoder::new(100);
+
+        assert_eq!(decoder.process(&NONE, at(0)), None);
+        assert_eq!(decoder.process(&INDEX, at(10)), None);
+        assert_eq!(decoder.pending_code(), Some(0b00010));
+        assert_eq!(decoder.process(&INDEX_MIDDLE, at(60)), None);
+        assert_eq!(decoder.remaining_ms(at(60)), Some(50));
+        assert_eq!(decoder.process(&INDEX_MIDDLE, at(110)), Some(0b00110));
+        assert_eq!(decoder.pending_code(), None);
+    }
+
+    #[test]
+    fn released_finger_stays_in_the_chord() {
+        let mut decoder = ChordDecoder::new(100);
+
+        decoder.process(&INDEX_MIDDLE, at(0));
+        decoder.process(&INDEX, at(50));
+        assert_eq!(decoder.process(&NONE, at(100)), Some(0b00110));
+    }
+
+    #[test]
+    fn next_chord_waits_for_the_release() {
+        let mut decoder = ChordDecoder::new(100);
+        decoder.process(&INDEX, at(0));
+        assert_eq!(decoder.process(&INDEX, at(100)), Some(0b00010));
+
+        // Still held, it does not start a new chord
+        assert_eq!(decoder.process(&INDEX_MIDDLE, at(200)), None);
+        assert_eq!(decoder.process(&INDEX_MIDDLE, at(300)), None);
+        assert_eq!(decoder.pending_code(), None);
+
+        assert_eq!(decoder.process(&NONE, at(310)), None);
+        assert_eq!(decoder.process(&INDEX, at(320)), None);
+        assert_eq!(decoder.process(&INDEX, at(420)), Some(0b00010));
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    PatternAction, StepKind, DEFAULT_CHORD_SETTLE_MS, DEFAULT_PATTERN_MAX_DELAY,
+    DEFAULT_REPEATING_PATTERN_DELAY,
+};
+
+/// Patterns and their actions, loaded from a toml or json file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PatternConfig {
+    #[serde(default)]
+    pub patterns: Vec<PatternDefinition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PatternDefinition {
+    pub name: String,
+    pub steps: Vec<StepDefinition>,
+    /// How many times in a row the steps must be performed
+    #[serde(default = "default_repetitions")]
+    pub repetitions: u32,
+    /// Maximum delay between two repetitions
+    #[serde(default = "default_repetition_delay_ms")]
+    pub repetition_delay_ms: u32,
+    /// The fingers of a chord can start moving this long apart
+    #[serde(default = "default_chord_settle_ms")]
+    pub chord_settle_ms: u32,
+    #[serde(default)]
+    pub actions: Vec<PatternAction>,
+}
+
+/// Fingers moving together (a single finger or a chord), released, or a recorded gesture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepDefinition {
+    /// From 0 (thumb) to 4 (little finger)
+    #[serde(default)]
+    pub fingers: Vec<u8>,
+    /// Name of a gesture template to perform instead of moving fingers
+    #[serde(default)]
+    pub gesture: Option<String>,
+    /// How long the fingers must stay moving
+    #[serde(default)]
+    pub hold_ms: u32,
+    /// The fingers must stop moving, all of them when no finger is given
+    #[serde(default)]
+    pub release: bool,
+    /// Maximum delay since the previous step
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u32,
+}
+
+fn default_repetitions() -> u32 {
+    1
+}
+
+fn default_repetition_delay_ms() -> u32 {
+    DEFAULT_REPEATING_PATTERN_DELAY
+}
+
+fn default_chord_settle_ms() -> u32 {
+    DEFAULT_CHORD_SETTLE_MS
+}
+
+fn default_max_delay_ms() -> u32 {
+    DEFAULT_PATTERN_MAX_DELAY
+}
+
+impl PatternConfig {
+    /// The format is chosen from the extension, toml unless it is `.json`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to open pattern config {}", path.display()))?;
+
+        let config: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&content)
+                .with_context(|| format!("invalid pattern config {}", path.display()))?
+        } else {
+            toml::from_str(&content)
+                .with_context(|| format!("invalid pattern config {}", path.display()))?
+        };
+
+        config
+            .validate()
+            .with_context(|| format!("invalid pattern config {}", path.display()))?;
+
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (index, pattern) in self.patterns.iter().enumerate() {
+            pattern
+                .validate()
+                .with_context(|| format!("patterns[{index}] \"{}\"", pattern.name))?;
+
+            if self.patterns[..index]
+                .iter()
+                .any(|other| other.name == pattern.name)
+            {
+                bail!(
+                    "patterns[{index}] \"{}\": the name is already used",
+                    pattern.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PatternDefinition {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.name.is_empty() {
+            bail!("the name is empty");
+        }
+
+        if !self.steps.iter().any(|step| !step.release) {
+            bail!("the pattern must contain at least one step that is not a release");
+        }
+
+        for (index, step) in self.steps.iter().enumerate() {
+            step.validate().with_context(|| format!("steps[{index}]"))?;
+        }
+
+        for (index, action) in self.actions.iter().enumerate() {
+            action
+                .validate()
+                .with_context(|| format!("actions[{index}]"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PatternDefinition {
+    /// Commands start any program, a pattern config only runs them when explicitly allowed
+    pub fn check_commands_allowed(&self, allow_commands: bool) -> anyhow::Result<()> {
+        let has_command = self
+            .actions
+            .iter()
+            .any(|action| matches!(action, PatternAction::Command { .. }));
+
+        if has_command && !allow_commands {
+            bail!(
+                "the pattern \"{}\" runs a command, commands must be explicitly allowed",
+                self.name
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl StepDefinition {
+    pub fn kind(&self) -> StepKind {
+        if let Some(gesture) = &self.gesture {
+            StepKind::Gesture {
+                name: gesture.clone(),
+            }
+        } else if self.release {
+            StepKind::Release
+        } else {
+            StepKind::Press {
+                min_hold_ms: self.hold_ms,
+            }
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(gesture) = &self.gesture {
+            if gesture.is_empty() {
+                bail!("the gesture name is empty");
+            }
+
+            // The template already tells the finger and how it moves
+            if !self.fingers.is_empty() || self.release || self.hold_ms > 0 {
+                bail!("a gesture step cannot have fingers, a release or a hold duration");
+            }
+
+            return Ok(());
+        }
+
+        if self.release && self.hold_ms > 0 {
+            bail!("a release step cannot have a hold duration");
+        }
+
+        if !self.release && self.fingers.is_empty() {
+            bail!("the step must contain at least one finger");
+        }
+
+        if let Some(finger) = self.fingers.iter().find(|finger| **finger > 4) {
+            bail!("invalid finger {finger}, expected 0 to 4");
+        }
+
+        Ok(())
+    }
+}
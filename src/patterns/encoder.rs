@@ -0,0 +1,263 @@
+use std::collections::VecDeque;
+
+use anyhow::bail;
+use chrono::{DateTime, Local, TimeDelta};
+use serde::Serialize;
+
+use crate::parser::{FingersFlexValues, FlexSensorGloveNotification};
+
+use super::{CharacterTable, Symbol, DEFAULT_TEXT_MAX_DELAY};
+
+/// Raw value of a finger at rest in synthetic notifications
+const SYNTHETIC_BASELINE: u32 = 1000;
+/// Raw value reached at the end of a press in synthetic notifications
+const SYNTHETIC_PRESS_AMPLITUDE: u32 = 300;
+
+/// One code of the character table and the fingers typing it.
+/// A code typed with a single finger is only applied once `TextPattern` times out
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodedCode {
+    pub code: u8,
+    /// From 0 (thumb) to 4 (little finger)
+    pub fingers: Vec<usize>,
+}
+
+impl EncodedCode {
+    pub fn new(code: u8) -> Self {
+        // Codes above 5 are `first * 5 + second`, hand values going from 1 to 5
+        let hand_values = if code <= 5 {
+            vec![code]
+        } else {
+            let first = (code - 1) / 5;
+            vec![first, code - first * 5]
+        };
+
+        Self {
+            code,
+            fingers: hand_values
+                .into_iter()
+                .map(|value| value as usize - 1)
+                .collect(),
+        }
+    }
+
+    pub fn waits_for_timeout(&self) -> bool {
+        self.fingers.len() == 1
+    }
+}
+
+/// Part of the text and the codes typing it, including the layer switches
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodedSymbol {
+    pub text: String,
+    pub codes: Vec<EncodedCode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerPress {
+    pub finger: usize,
+    pub start_ms: u32,
+    pub end_ms: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderTiming {
+    /// How long each finger is flexed
+    pub press_ms: u32,
+    /// Rest between the release of a finger and the next press
+    pub gap_ms: u32,
+    /// Rest after a single finger code, must be above the `TextPattern` maximum delay
+    pub timeout_ms: u32,
+}
+
+impl Default for EncoderTiming {
+    fn default() -> Self {
+        Self {
+            press_ms: 200,
+            gap_ms: 300,
+            timeout_ms: DEFAULT_TEXT_MAX_DELAY + 300,
+        }
+    }
+}
+
+/// Inverse of `TextPattern`, turns a text into the finger sequence typing it
+pub struct TextEncoder {
+    character_table: CharacterTable,
+    timing: EncoderTiming,
+}
+
+impl TextEncoder {
+    pub fn new(character_table: CharacterTable) -> Self {
+        Self {
+            character_table,
+            timing: EncoderTiming::default(),
+        }
+    }
+
+    pub fn timing(&mut self, timing: EncoderTiming) {
+        self.timing = timing;
+    }
+
+    /// Letters are matched regardless of their case, the longest symbol being typed first
+    pub fn encode(&self, text: &str) -> anyhow::Result<Vec<EncodedSymbol>> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut symbols = vec![];
+        let mut locked_layer = 0;
+        let mut position = 0;
+
+        while position < chars.len() {
+            let Some((length, codes, next_locked_layer)) =
+                self.find_symbol(&chars[position..], locked_layer)
+            else {
+                bail!(
+                    "{:?} cannot be typed with the {} character table",
+                    chars[position],
+                    self.character_table.name
+                );
+            };
+
+            symbols.push(EncodedSymbol {
+                text: chars[position..position + length].iter().collect(),
+                codes: codes.into_iter().map(EncodedCode::new).collect(),
+            });
+
+            position += length;
+            locked_layer = next_locked_layer;
+        }
+
+        Ok(symbols)
+    }
+
+    /// Searches the layers reachable from `locked_layer` for the longest symbol starting the text,
+    /// returns its length, the codes typing it and the locked layer afterwards
+    fn find_symbol(&self, text: &[char], locked_layer: usize) -> Option<(usize, Vec<u8>, usize)> {
+        let layers_count = self.character_table.layers.len();
+        let mut visited = vec![vec![false; layers_count]; layers_count];
+        let mut to_visit = VecDeque::from([(locked_layer, locked_layer, vec![])]);
+        let mut best: Option<(usize, Vec<u8>, usize)> = None;
+
+        // Breadth first, so the first path found to a layer is the shortest one
+        while let Some((current, locked, codes)) = to_visit.pop_front() {
+            if visited[current][locked] {
+                continue;
+            }
+            visited[current][locked] = true;
+
+            let layer = &self.character_table.layers[current];
+
+            for (position, symbol) in layer.symbols.iter().enumerate() {
+                let code = position as u8 + 1;
+
+                match symbol {
+                    Symbol::Text(symbol_text) => {
+                        let Some(length) = match_symbol(text, symbol_text) else {
+                            continue;
+                        };
+
+                        if best.as_ref().is_none_or(|best| length > best.0) {
+                            let mut codes = codes.clone();
+                            codes.push(code);
+                            best = Some((length, codes, locked));
+                        }
+                    }
+                    Symbol::Layer { layer, lock } => {
+                        let Some(target) = self.character_table.layer_index(layer) else {
+                            continue;
+                        };
+
+                        let mut codes = codes.clone();
+                        codes.push(code);
+
+                        let next_locked = if *lock { target } else { locked };
+                        to_visit.push_back((target, next_locked, codes));
+                    }
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Timeline of the finger presses, starting at 0
+    pub fn presses(&self, symbols: &[EncodedSymbol]) -> Vec<FingerPress> {
+        let mut presses = vec![];
+        let mut time = 0;
+
+        for code in symbols.iter().flat_map(|symbol| &symbol.codes) {
+            let code_start = time;
+
+            for finger in &code.fingers {
+                presses.push(FingerPress {
+                    finger: *finger,
+                    start_ms: time,
+                    end_ms: time + self.timing.press_ms,
+                });
+                time += self.timing.press_ms + self.timing.gap_ms;
+            }
+
+            if code.waits_for_timeout() {
+                time = time.max(code_start + self.timing.timeout_ms);
+            }
+        }
+
+        presses
+    }
+
+    /// Raw notifications of a glove performing the presses, with a rest period before and after
+    pub fn synthetic_notifications(
+        &self,
+        presses: &[FingerPress],
+        start: DateTime<Local>,
+        rate: f64,
+    ) -> Vec<FlexSensorGloveNotification> {
+        let rest_ms = self.timing.timeout_ms;
+        let end_ms = presses.iter().map(|press| press.end_ms).max().unwrap_or(0) + 2 * rest_ms;
+        let period_ms = 1000.0 / rate;
+
+        (0..)
+            .map(|i| i as f64 * period_ms)
+            .take_while(|time_ms| *time_ms <= end_ms as f64)
+            .map(|time_ms| {
+                let mut flex_values = FingersFlexValues([SYNTHETIC_BASELINE; 5]);
+
+                // The glove values rise while a finger is flexed and fall back at once on release
+                for press in presses {
+                    let press_time_ms = time_ms - (press.start_ms + rest_ms) as f64;
+                    let duration_ms = (press.end_ms - press.start_ms) as f64;
+
+                    if (0.0..duration_ms).contains(&press_time_ms) {
+                        flex_values.0[press.finger] +=
+                            (SYNTHETIC_PRESS_AMPLITUDE as f64 * press_time_ms / duration_ms) as u32;
+                    }
+                }
+
+                FlexSensorGloveNotification {
+                    dt: start + TimeDelta::microseconds((time_ms * 1000.0) as i64),
+                    flex_values,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns how many characters of the text the symbol matches, ignoring the case
+fn match_symbol(text: &[char], symbol: &str) -> Option<usize> {
+    if symbol.is_empty() {
+        return None;
+    }
+
+    let mut length = 0;
+    for symbol_char in symbol.chars() {
+        let text_char = text.get(length)?;
+
+        if !text_char.to_lowercase().eq(symbol_char.to_lowercase()) {
+            return None;
+        }
+        length += 1;
+    }
+
+    Some(length)
+}
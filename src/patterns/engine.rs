@@ -0,0 +1,226 @@
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    GestureMatch, Pattern, PatternAction, PatternDefinition, PatternStep, ReapeatingPattern,
+    StepDefinition, DEFAULT_CHORD_SETTLE_MS, DEFAULT_PATTERN_MAX_DELAY,
+    DEFAULT_REPEATING_PATTERN_DELAY,
+};
+
+/// Named finger sequence the engine looks for, e.g. `marker=0,4x2` on the command line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternSpec {
+    pub name: String,
+    /// From 0 (thumb) to 4 (little finger)
+    pub fingers_order: Vec<u8>,
+    /// How many times in a row the sequence must be performed
+    pub repetitions: u32,
+}
+
+impl std::str::FromStr for PatternSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, sequence) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected <name>=<fingers>[x<repetitions>], got {s}"))?;
+
+        let (fingers, repetitions) = sequence.split_once('x').unwrap_or((sequence, "1"));
+
+        let fingers_order = fingers
+            .split(',')
+            .map(|finger| finger.trim().parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid fingers in pattern {name}: {e}"))?;
+
+        let repetitions = repetitions
+            .parse()
+            .map_err(|e| format!("invalid repetitions in pattern {name}: {e}"))?;
+
+        Ok(Self {
+            name: name.trim().to_string(),
+            fingers_order,
+            repetitions,
+        })
+    }
+}
+
+impl From<&PatternSpec> for PatternDefinition {
+    fn from(spec: &PatternSpec) -> Self {
+        Self {
+            name: spec.name.clone(),
+            steps: spec
+                .fingers_order
+                .iter()
+                .map(|finger| StepDefinition {
+                    fingers: vec![*finger],
+                    gesture: None,
+                    hold_ms: 0,
+                    release: false,
+                    max_delay_ms: DEFAULT_PATTERN_MAX_DELAY,
+                })
+                .collect(),
+            repetitions: spec.repetitions,
+            repetition_delay_ms: DEFAULT_REPEATING_PATTERN_DELAY,
+            chord_settle_ms: DEFAULT_CHORD_SETTLE_MS,
+            actions: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PatternDetection {
+    pub name: String,
+    pub repetitions: u32,
+    /// When the participant started performing the pattern
+    pub started_at: DateTime<Local>,
+    pub actions: Vec<PatternAction>,
+}
+
+/// How far into a pattern the participant is
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternProgress {
+    pub name: String,
+    /// Index of the next step to perform
+    pub step: usize,
+    pub steps: usize,
+    /// Repetitions already performed
+    pub repetition: u32,
+    pub repetitions: u32,
+    /// Time left to perform the next step or repetition before the pattern is reset
+    pub remaining_ms: Option<i64>,
+}
+
+struct EnginePattern {
+    name: String,
+    pattern: ReapeatingPattern,
+    repetitions: u32,
+    actions: Vec<PatternAction>,
+    /// Step and repetition of the last reported progress
+    reported_progress: (usize, u32),
+}
+
+/// Runs any number of named sequence patterns on the moving fingers
+#[derive(Default)]
+pub struct PatternEngine {
+    patterns: Vec<EnginePattern>,
+}
+
+impl PatternEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_definitions(definitions: &[PatternDefinition]) -> anyhow::Result<Self> {
+        let mut engine = Self::new();
+
+        for definition in definitions {
+            engine
+                .add_pattern(definition)
+                .with_context(|| format!("invalid pattern {}", definition.name))?;
+        }
+
+        Ok(engine)
+    }
+
+    pub fn add_pattern(&mut self, definition: &PatternDefinition) -> anyhow::Result<()> {
+        definition.validate()?;
+
+        let steps = definition
+            .steps
+            .iter()
+            .map(|step| {
+                let mut fingers = [false; 5];
+                for finger in &step.fingers {
+                    fingers[*finger as usize] = true;
+                }
+
+                PatternStep {
+                    fingers,
+                    kind: step.kind(),
+                    max_ms_delay: step.max_delay_ms,
+                }
+            })
+            .collect();
+
+        let mut pattern = Pattern::from_steps(steps);
+        pattern.chord_settle_ms(definition.chord_settle_ms);
+
+        self.patterns.push(EnginePattern {
+            name: definition.name.clone(),
+            pattern: ReapeatingPattern::new(pattern, definition.repetition_delay_ms),
+            repetitions: definition.repetitions.max(1),
+            actions: definition.actions.clone(),
+            reported_progress: (0, 0),
+        });
+
+        Ok(())
+    }
+
+    /// Returns the patterns performed the required number of times,
+    /// `gestures` being the gestures recognized at the same time as the moving fingers
+    pub fn process(
+        &mut self,
+        moved_fingers: &[bool; 5],
+        gestures: &[GestureMatch],
+        time: DateTime<Local>,
+    ) -> Vec<PatternDetection> {
+        let mut detections = vec![];
+
+        for engine_pattern in self.patterns.iter_mut() {
+            engine_pattern
+                .pattern
+                .process(moved_fingers, gestures, time);
+
+            if engine_pattern.pattern.nb_done >= engine_pattern.repetitions {
+                let started_at = engine_pattern.pattern.started_at().unwrap_or(time);
+                engine_pattern.pattern.nb_done = 0;
+
+                detections.push(PatternDetection {
+                    name: engine_pattern.name.clone(),
+                    repetitions: engine_pattern.repetitions,
+                    started_at,
+                    actions: engine_pattern.actions.clone(),
+                });
+            }
+        }
+
+        detections
+    }
+
+    /// Drops the steps and repetitions performed so far
+    pub fn reset(&mut self) {
+        for engine_pattern in self.patterns.iter_mut() {
+            engine_pattern.pattern.reset();
+        }
+    }
+
+    /// Returns the progress of the patterns whose step or repetition changed since the last call
+    pub fn take_progress_changes(&mut self, time: DateTime<Local>) -> Vec<PatternProgress> {
+        let mut changes = vec![];
+
+        for engine_pattern in self.patterns.iter_mut() {
+            let pattern = engine_pattern.pattern.pattern();
+            let progress = (pattern.current_step(), engine_pattern.pattern.nb_done);
+
+            if progress == engine_pattern.reported_progress {
+                continue;
+            }
+
+            engine_pattern.reported_progress = progress;
+            changes.push(PatternProgress {
+                name: engine_pattern.name.clone(),
+                step: progress.0,
+                steps: pattern.steps_count(),
+                repetition: progress.1,
+                repetitions: engine_pattern.repetitions,
+                remaining_ms: engine_pattern.pattern.remaining_ms(time),
+            });
+        }
+
+        changes
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+
+use crate::{opt::FingersSensibility, parser::FingersFlexValues};
+
+/// How far back the peak of each finger is looked for when a hand position is detected
+pub const DEFAULT_EVIDENCE_WINDOW_MS: u32 = 250;
+
+/// Slope of the flex probability around the threshold, a finger reaching twice its threshold
+/// is flexed with a probability of 0.98
+const FLEX_PROBABILITY_STEEPNESS: f32 = 4.0;
+
+/// Keeps the recent values of each finger relative to its detection threshold,
+/// to tell a clean movement from a borderline threshold crossing
+pub struct FingerEvidence {
+    thresholds: [f32; 5],
+    window_ms: u32,
+    samples: VecDeque<(DateTime<Local>, [f32; 5])>,
+}
+
+impl FingerEvidence {
+    pub fn new(sensibility: &FingersSensibility) -> Self {
+        Self {
+            thresholds: sensibility.0.map(|sensibility| sensibility.max(1) as f32),
+            window_ms: DEFAULT_EVIDENCE_WINDOW_MS,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// The values already pushed stay relative to the previous thresholds until they expire
+    pub fn set_sensibility(&mut self, sensibility: &FingersSensibility) {
+        self.thresholds = sensibility.0.map(|sensibility| sensibility.max(1) as f32);
+    }
+
+    pub fn window_ms(&mut self, window_ms: u32) {
+        self.window_ms = window_ms;
+    }
+
+    pub fn push(&mut self, values: &FingersFlexValues, time: DateTime<Local>) {
+        let mut ratios = [0.0; 5];
+        for (i, ratio) in ratios.iter_mut().enumerate() {
+            *ratio = values.0[i] as f32 / self.thresholds[i];
+        }
+
+        self.samples.push_back((time, ratios));
+
+        while let Some((oldest, _)) = self.samples.front() {
+            if time.signed_duration_since(*oldest).num_milliseconds() <= self.window_ms as i64 {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Highest value of each finger over the window, 1 being its threshold
+    pub fn peaks(&self) -> [f32; 5] {
+        let mut peaks = [0.0_f32; 5];
+        for (_, ratios) in &self.samples {
+            for (peak, ratio) in peaks.iter_mut().zip(ratios) {
+                *peak = peak.max(*ratio);
+            }
+        }
+
+        peaks
+    }
+
+    /// Probability of each finger being flexed, 0.5 right at its threshold
+    pub fn flex_probabilities(&self) -> [f32; 5] {
+        self.peaks()
+            .map(|peak| 1.0 / (1.0 + (-FLEX_PROBABILITY_STEEPNESS * (peak - 1.0)).exp()))
+    }
+
+    /// Probability of each finger being the one flexed alone for a hand position
+    pub fn position_probabilities(&self) -> [f32; 5] {
+        let flex_probabilities = self.flex_probabilities();
+
+        let mut weights = [0.0; 5];
+        for (finger, weight) in weights.iter_mut().enumerate() {
+            *weight = flex_probabilities
+                .iter()
+                .enumerate()
+                .map(|(other, p)| if other == finger { *p } else { 1.0 - p })
+                .product();
+        }
+
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return [0.2; 5];
+        }
+
+        weights.map(|weight| weight / total)
+    }
+}
+
+/// Probability of every code typed with the given hand positions, codes being
+/// `first * 5 + second` with hand values from 1 to 5
+pub fn position_code_probabilities(positions: &[[f32; 5]]) -> Vec<(u8, f32)> {
+    match positions {
+        [single] => (0..5).map(|f| (f as u8 + 1, single[f])).collect(),
+        [first, second] => (0..5)
+            .flat_map(|a| {
+                (0..5).map(move |b| ((a as u8 + 1) * 5 + b as u8 + 1, first[a] * second[b]))
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Probability of every chord knowing one was typed, the thumb being the lowest bit of the code
+pub fn chord_code_probabilities(flex_probabilities: &[f32; 5]) -> Vec<(u8, f32)> {
+    let probabilities: Vec<(u8, f32)> = (1..32_u8)
+        .map(|code| {
+            let probability = flex_probabilities
+                .iter()
+                .enumerate()
+                .map(|(finger, p)| {
+                    if code & (1 << finger) != 0 {
+                        *p
+                    } else {
+                        1.0 - p
+                    }
+                })
+                .product();
+
+            (code, probability)
+        })
+        .collect();
+
+    let total: f32 = probabilities.iter().map(|(_, p)| p).sum();
+    if total <= 0.0 {
+        return vec![];
+    }
+
+    probabilities
+        .into_iter()
+        .map(|(code, p)| (code, p / total))
+        .collect()
+}
@@ -0,0 +1,348 @@
+use anyhow::bail;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::ProcessEvent;
+
+use super::{CharacterTable, Symbol, TextEncoder, TypedCharacter};
+
+pub const DEFAULT_MESSAGE_MIN_CONFIDENCE: f32 = 0.8;
+pub const DEFAULT_MESSAGE_TIMEOUT_MS: u32 = 120_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageFramingConfig {
+    /// Name of the pattern starting a message
+    pub start_pattern: String,
+    /// Name of the pattern ending a message, it can be the start one
+    pub end_pattern: String,
+    /// A message with a character typed with a lower confidence is suspect
+    #[serde(default = "default_min_confidence")]
+    pub min_confidence: f32,
+    /// A message not ended after this long is reported as suspect
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u32,
+}
+
+fn default_min_confidence() -> f32 {
+    DEFAULT_MESSAGE_MIN_CONFIDENCE
+}
+
+fn default_timeout_ms() -> u32 {
+    DEFAULT_MESSAGE_TIMEOUT_MS
+}
+
+impl MessageFramingConfig {
+    pub fn new(start_pattern: String, end_pattern: String) -> Self {
+        Self {
+            start_pattern,
+            end_pattern,
+            min_confidence: DEFAULT_MESSAGE_MIN_CONFIDENCE,
+            timeout_ms: DEFAULT_MESSAGE_TIMEOUT_MS,
+        }
+    }
+
+    /// The framing patterns must be among the patterns looked for
+    pub fn validate(&self, pattern_names: &[&str]) -> anyhow::Result<()> {
+        for name in [&self.start_pattern, &self.end_pattern] {
+            if !pattern_names.contains(&name.as_str()) {
+                bail!("the message framing pattern {name:?} is not defined");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Why a message cannot be trusted
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum MessageIssue {
+    /// Nothing was typed between the start and the end of the message
+    MissingCheckSymbol,
+    CheckSymbolMismatch {
+        expected: String,
+        typed: String,
+    },
+    /// The text or its check code cannot be typed as text with the character table
+    UncheckableText,
+    LowConfidence {
+        position: usize,
+        text: String,
+        confidence: f32,
+    },
+    /// The end signal was not received, the message is reported as it was when it stopped
+    NotEnded,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceivedMessage {
+    pub text: String,
+    pub check_symbol: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub verified: bool,
+    pub issues: Vec<MessageIssue>,
+}
+
+struct OpenMessage {
+    started_at: DateTime<Local>,
+    characters: Vec<(DateTime<Local>, TypedCharacter)>,
+}
+
+/// Delimits the messages typed between a start and an end pattern. The last character before the
+/// end pattern is a check symbol: the letter typed with the single finger code `sum % 5 + 1`.
+/// `sum` adds up the hand positions, from 1 to 5, of the codes typing the characters of the
+/// message in the character table, weighted 1, 2, 3, 4, 1, 2... by their rank. 5 being prime,
+/// any single wrong hand position, first or second, changes the check symbol
+pub struct MessageFraming {
+    config: MessageFramingConfig,
+    character_table: CharacterTable,
+    encoder: TextEncoder,
+
+    message: Option<OpenMessage>,
+}
+
+impl MessageFraming {
+    pub fn new(config: MessageFramingConfig, character_table: CharacterTable) -> Self {
+        Self {
+            config,
+            encoder: TextEncoder::new(character_table.clone()),
+            character_table,
+
+            message: None,
+        }
+    }
+
+    pub fn config(&self) -> &MessageFramingConfig {
+        &self.config
+    }
+
+    pub fn is_receiving(&self) -> bool {
+        self.message.is_some()
+    }
+
+    /// The symbol ending a message made of `text`, `None` when the text or the check code
+    /// cannot be typed as text
+    pub fn check_symbol(&self, text: &str) -> Option<String> {
+        // Only the code typing each character counts, not the layer switches leading to it
+        let sum: usize = self
+            .encoder
+            .encode(text)
+            .ok()?
+            .iter()
+            .filter_map(|symbol| symbol.codes.last())
+            .flat_map(|code| code.fingers.iter().map(|finger| finger + 1))
+            .enumerate()
+            .map(|(rank, hand_value)| (rank % 4 + 1) * hand_value)
+            .sum();
+        let code = sum % 5 + 1;
+
+        match self.character_table.symbol(0, code as u8)? {
+            Symbol::Text(text) => Some(text.clone()),
+            Symbol::Layer { .. } => None,
+        }
+    }
+
+    /// `started_at` is when the participant started performing the pattern,
+    /// the characters typed since belong to the signal and not to the message
+    pub fn pattern_detected(
+        &mut self,
+        name: &str,
+        started_at: DateTime<Local>,
+        time: DateTime<Local>,
+    ) -> Vec<ProcessEvent> {
+        let mut events = vec![];
+
+        if self.message.is_some() && name == self.config.end_pattern {
+            if let Some(message) = self.message.take() {
+                events.push(self.receive(message, started_at, time, vec![]));
+            }
+
+            return events;
+        }
+
+        if name != self.config.start_pattern {
+            return events;
+        }
+
+        if let Some(message) = self.message.take() {
+            events.push(self.receive(message, started_at, time, vec![MessageIssue::NotEnded]));
+        }
+
+        self.message = Some(OpenMessage {
+            started_at: time,
+            characters: vec![],
+        });
+        events.push(ProcessEvent::MessageStarted);
+
+        events
+    }
+
+    pub fn push(&mut self, character: &TypedCharacter, time: DateTime<Local>) {
+        let Some(message) = self.message.as_mut() else {
+            return;
+        };
+
+        if character.text == "\u{8}" {
+            message.characters.pop();
+            return;
+        }
+
+        message.characters.push((time, character.clone()));
+    }
+
+    /// Reports the message once it timed out
+    pub fn process(&mut self, time: DateTime<Local>) -> Option<ProcessEvent> {
+        let started_at = self.message.as_ref()?.started_at;
+        let elapsed_ms = time.signed_duration_since(started_at).num_milliseconds();
+
+        if elapsed_ms < self.config.timeout_ms as i64 {
+            return None;
+        }
+
+        let message = self.message.take()?;
+        Some(self.receive(message, time, time, vec![MessageIssue::NotEnded]))
+    }
+
+    fn receive(
+        &self,
+        message: OpenMessage,
+        signal_started_at: DateTime<Local>,
+        time: DateTime<Local>,
+        mut issues: Vec<MessageIssue>,
+    ) -> ProcessEvent {
+        let mut characters: Vec<TypedCharacter> = message
+            .characters
+            .into_iter()
+            .filter(|(typed_at, _)| *typed_at < signal_started_at)
+            .map(|(_, character)| character)
+            .collect();
+
+        for (position, character) in characters.iter().enumerate() {
+            let Some(confidence) = character.confidence else {
+                continue;
+            };
+
+            if confidence < self.config.min_confidence {
+                issues.push(MessageIssue::LowConfidence {
+                    position,
+                    text: character.text.clone(),
+                    confidence,
+                });
+            }
+        }
+
+        let check_symbol = characters.pop().map(|character| character.text);
+        let text: String = characters
+            .iter()
+            .map(|character| character.text.as_str())
+            .collect();
+
+        match (&check_symbol, self.check_symbol(&text)) {
+            (None, _) => issues.push(MessageIssue::MissingCheckSymbol),
+            (Some(typed), Some(expected)) if typed.to_lowercase() != expected.to_lowercase() => {
+                issues.push(MessageIssue::CheckSymbolMismatch {
+                    expected,
+                    typed: typed.clone(),
+                })
+            }
+            (Some(_), None) => issues.push(MessageIssue::UncheckableText),
+            _ => {}
+        }
+
+        ProcessEvent::MessageReceived(ReceivedMessage {
+            text,
+            check_symbol,
+            started_at: message.started_at,
+            ended_at: time,
+            verified: issues.is_empty(),
+            issues,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::super::CharacterLayer;
+    use super::*;
+
+    /// Letters a to l typed with the codes 1 to 12, the code 7 is the hand positions 1 then 2
+    /// and the code 11 is 2 then 1
+    fn framing() -> MessageFraming {
+        let symbols = ('a'..='l')
+            .map(|letter| Symbol::Text(letter.to_string()))
+            .collect();
+        let character_table = CharacterTable {
+            name: "test".to_string(),
+            layers: vec![CharacterLayer {
+                name: "letters".to_string(),
+                symbols,
+            }],
+        };
+
+        MessageFraming::new(
+            MessageFramingConfig::new("start".to_string(), "end".to_string()),
+            character_table,
+        )
+    }
+
+    fn at(ms: i64) -> DateTime<Local> {
+        Local.timestamp_millis_opt(1_000_000 + ms).unwrap()
+    }
+
+    #[test]
+    fn check_symbol_weights_the_hand_positions_by_rank() {
+        let framing = framing();
+
+        // 3 -> 3 % 5 + 1
+        assert_eq!(framing.check_symbol("c").as_deref(), Some("d"));
+        // 1 * 1 + 2 * 2 -> 5 % 5 + 1
+        assert_eq!(framing.check_symbol("g").as_deref(), Some("a"));
+        // 1 * 2 + 2 * 1 -> 4 % 5 + 1
+        assert_eq!(framing.check_symbol("k").as_deref(), Some("e"));
+        // 1 * 3 + 2 * 1 + 3 * 2 + 4 * 2 + 1 * 1 + 2 * 1 -> 22 % 5 + 1
+        assert_eq!(framing.check_symbol("cgka").as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn single_wrong_hand_position_changes_the_check_symbol() {
+        let framing = framing();
+        let expected = framing.check_symbol("cgka");
+
+        for text in ["bgka", "ckka", "cfka", "cgla", "cgkb"] {
+            assert_ne!(framing.check_symbol(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn untypable_text_has_no_check_symbol() {
+        assert_eq!(framing().check_symbol("z"), None);
+    }
+
+    #[test]
+    fn message_ending_with_its_check_symbol_is_verified() {
+        let mut framing = framing();
+
+        framing.pattern_detected("start", at(0), at(100));
+        for (time, text) in [(200, "c"), (300, "g"), (400, "b")] {
+            framing.push(&TypedCharacter::new(text.to_string()), at(time));
+        }
+        let events = framing.pattern_detected("end", at(500), at(600));
+
+        let [ProcessEvent::MessageReceived(message)] = events.as_slice() else {
+            panic!("expected a received message, got {events:?}");
+        };
+        // 1 * 3 + 2 * 1 + 3 * 2 -> 11 % 5 + 1
+        assert_eq!(message.text, "cg");
+        assert_eq!(message.check_symbol.as_deref(), Some("b"));
+        assert!(message.verified, "{:?}", message.issues);
+    }
+}
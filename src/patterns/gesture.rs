@@ -0,0 +1,327 @@
+use std::{collections::VecDeque, path::Path};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aggregator::MeanAggregator,
+    parser::{FingersFlexValues, FlexSensorGloveNotification},
+    resampler::{check_resample_rate, Resampler},
+};
+
+pub const DEFAULT_GESTURE_MIN_SIMILARITY: f32 = 0.6;
+
+/// The window must reach this part of the template amplitude to be compared,
+/// otherwise the normalization would make noise look like any shape
+const MIN_AMPLITUDE_RATIO: f32 = 0.5;
+
+/// Reference shape of a gesture made with a single finger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GestureTemplate {
+    pub name: String,
+    /// From 0 (thumb) to 4 (little finger)
+    pub finger: usize,
+    /// Aggregated values of the finger during the example
+    pub samples: Vec<u32>,
+    /// Rate the example was resampled at, the recognition should use the same
+    #[serde(default)]
+    pub resample_rate: Option<f64>,
+    /// Similarity from 0 to 1 above which the gesture is detected
+    pub min_similarity: f32,
+}
+
+impl GestureTemplate {
+    /// Extracts the template from an example recorded between `start` and `end`,
+    /// the recording being resampled and aggregated as it is before recognition
+    pub fn from_recording(
+        name: String,
+        finger: usize,
+        recording: &[FlexSensorGloveNotification],
+        start: DateTime<Local>,
+        end: DateTime<Local>,
+        mut resampler: Option<Resampler>,
+        aggregation_size: usize,
+    ) -> anyhow::Result<Self> {
+        if finger > 4 {
+            bail!("invalid finger {finger}, expected 0 to 4");
+        }
+
+        let resample_rate = resampler.as_ref().map(Resampler::rate);
+        let recording = match resampler.as_mut() {
+            Some(resampler) => resampler.resample(recording),
+            None => recording.to_vec(),
+        };
+
+        let mut aggregator = (aggregation_size > 0).then(|| MeanAggregator::new(aggregation_size));
+
+        let samples: Vec<u32> = recording
+            .into_iter()
+            .map(|notification| match aggregator.as_mut() {
+                Some(aggregator) => aggregator.push_and_aggregate(notification),
+                None => notification,
+            })
+            .filter(|notification| start <= notification.dt && notification.dt <= end)
+            .map(|notification| notification.flex_values.0[finger])
+            .collect();
+
+        let template = Self {
+            name,
+            finger,
+            samples,
+            resample_rate,
+            min_similarity: DEFAULT_GESTURE_MIN_SIMILARITY,
+        };
+        template.validate()?;
+
+        Ok(template)
+    }
+
+    pub fn load_all(path: &Path) -> anyhow::Result<Vec<Self>> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("unable to open gestures {}", path.display()))?;
+
+        let templates: Vec<Self> = serde_json::from_reader(file)
+            .with_context(|| format!("invalid gestures {}", path.display()))?;
+
+        for (index, template) in templates.iter().enumerate() {
+            template.validate().with_context(|| {
+                format!(
+                    "invalid gestures {}: [{index}] \"{}\"",
+                    path.display(),
+                    template.name
+                )
+            })?;
+        }
+
+        Ok(templates)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.finger > 4 {
+            bail!("invalid finger {}, expected 0 to 4", self.finger);
+        }
+
+        if self.samples.len() < 2 {
+            bail!("the gesture example must contain at least 2 samples");
+        }
+
+        // A flat template would match any window once normalized
+        if self.amplitude() == 0 {
+            bail!("the gesture example does not move");
+        }
+
+        if !(self.min_similarity > 0.0 && self.min_similarity <= 1.0) {
+            bail!(
+                "invalid minimum similarity {}, expected a value above 0 and up to 1",
+                self.min_similarity
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The samples are compared one by one, they must be taken at the same rate
+    pub fn check_resample_rate(&self, resample_rate: Option<f64>) -> anyhow::Result<()> {
+        check_resample_rate(
+            &format!("gesture \"{}\"", self.name),
+            self.resample_rate,
+            resample_rate,
+        )
+    }
+
+    pub fn save_all(templates: &[Self], path: &Path) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("unable to create gestures {}", path.display()))?;
+
+        serde_json::to_writer_pretty(file, templates)?;
+        Ok(())
+    }
+
+    fn amplitude(&self) -> u32 {
+        let min = self.samples.iter().min().copied().unwrap_or_default();
+        let max = self.samples.iter().max().copied().unwrap_or_default();
+        max - min
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GestureMatch {
+    pub name: String,
+    pub finger: usize,
+    pub similarity: f32,
+}
+
+struct TemplateState {
+    template: GestureTemplate,
+    normalized: Vec<f32>,
+    min_amplitude: u32,
+    /// Samples to wait before this template can match again
+    cooldown: usize,
+    /// Best similarity while above the threshold, the match is reported once it goes back below
+    candidate: Option<f32>,
+}
+
+/// Matches the incoming stream of each finger against gesture templates with dynamic time warping.
+/// The matches are only reported as [`crate::ProcessEvent::GestureDetected`] events,
+/// the text and pattern decoders work on the moving fingers and do not consume them
+pub struct GestureRecognizer {
+    templates: Vec<TemplateState>,
+    windows: [VecDeque<u32>; 5],
+    window_size: usize,
+}
+
+impl GestureRecognizer {
+    pub fn new(templates: Vec<GestureTemplate>) -> Self {
+        let window_size = templates
+            .iter()
+            .map(|template| template.samples.len())
+            .max()
+            .unwrap_or_default();
+
+        Self {
+            templates: templates
+                .into_iter()
+                .map(|template| TemplateState {
+                    normalized: z_normalize(template.samples.iter()),
+                    min_amplitude: (template.amplitude() as f32 * MIN_AMPLITUDE_RATIO) as u32,
+                    cooldown: 0,
+                    candidate: None,
+                    template,
+                })
+                .collect(),
+            windows: Default::default(),
+            window_size,
+        }
+    }
+
+    pub fn process(&mut self, values: &FingersFlexValues) -> Vec<GestureMatch> {
+        for (finger, window) in self.windows.iter_mut().enumerate() {
+            if window.len() >= self.window_size {
+                window.pop_front();
+            }
+            window.push_back(values.0[finger]);
+        }
+
+        let mut matches = vec![];
+
+        for state in self.templates.iter_mut() {
+            if state.cooldown > 0 {
+                state.cooldown -= 1;
+                continue;
+            }
+
+            let window = &self.windows[state.template.finger];
+            let len = state.template.samples.len();
+            if window.len() < len {
+                continue;
+            }
+
+            let recent = window.range(window.len() - len..);
+            let min = recent.clone().min().copied().unwrap_or_default();
+            let max = recent.clone().max().copied().unwrap_or_default();
+
+            let similarity = if max - min < state.min_amplitude {
+                0.0
+            } else {
+                1.0 / (1.0 + dtw_distance(&state.normalized, &z_normalize(recent)))
+            };
+
+            match state.candidate {
+                Some(best) if similarity >= state.template.min_similarity => {
+                    state.candidate = Some(best.max(similarity));
+                }
+                Some(best) => {
+                    state.candidate = None;
+                    state.cooldown = len;
+                    matches.push(GestureMatch {
+                        name: state.template.name.clone(),
+                        finger: state.template.finger,
+                        similarity: best,
+                    });
+                }
+                None if similarity >= state.template.min_similarity => {
+                    state.candidate = Some(similarity);
+                }
+                None => {}
+            }
+        }
+
+        matches
+    }
+}
+
+fn z_normalize<'a>(values: impl Iterator<Item = &'a u32> + Clone) -> Vec<f32> {
+    let len = values.clone().count() as f32;
+    let mean = values.clone().map(|v| *v as f32).sum::<f32>() / len;
+    let std = (values
+        .clone()
+        .map(|v| (*v as f32 - mean).powi(2))
+        .sum::<f32>()
+        / len)
+        .sqrt()
+        .max(f32::EPSILON);
+
+    values.map(|v| (*v as f32 - mean) / std).collect()
+}
+
+/// Mean absolute difference along the best warping path, constrained to a Sakoe-Chiba band
+fn dtw_distance(a: &[f32], b: &[f32]) -> f32 {
+    let (n, m) = (a.len(), b.len());
+    let band = (n.max(m) / 4).max(n.abs_diff(m)).max(1);
+
+    // cost and path length of the best path ending on each cell
+    let mut previous = vec![(f32::INFINITY, 0usize); m + 1];
+    let mut current = vec![(f32::INFINITY, 0usize); m + 1];
+    previous[0] = (0.0, 0);
+
+    for i in 1..=n {
+        current.fill((f32::INFINITY, 0));
+
+        for j in i.saturating_sub(band).max(1)..=(i + band).min(m) {
+            let cost = (a[i - 1] - b[j - 1]).abs();
+            let best = [previous[j - 1], previous[j], current[j - 1]]
+                .into_iter()
+                .min_by(|x, y| x.0.total_cmp(&y.0))
+                .unwrap_or((f32::INFINITY, 0));
+
+            current[j] = (best.0 + cost, best.1 + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let (cost, length) = previous[m];
+    cost / length.max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dtw_distance_of_the_same_shape_is_zero() {
+        let shape = [0.0, 0.5, 1.0, 0.5, 0.0];
+
+        assert_eq!(dtw_distance(&shape, &shape), 0.0);
+    }
+
+    #[test]
+    fn dtw_distance_follows_a_slower_gesture() {
+        let shape = [0.0, 0.5, 1.0, 0.5, 0.0];
+        let slower = [0.0, 0.0, 0.5, 0.5, 1.0, 1.0, 0.5, 0.5, 0.0, 0.0];
+
+        assert_eq!(dtw_distance(&shape, &slower), 0.0);
+    }
+
+    #[test]
+    fn dtw_distance_is_the_mean_difference_along_the_path() {
+        let shape = [0.0, 1.0, 0.0];
+        let flat = [0.0, 0.0, 0.0];
+
+        assert!((dtw_distance(&shape, &flat) - 1.0 / 3.0).abs() < 1e-6);
+        assert!(dtw_distance(&shape, &flat) < dtw_distance(&shape, &[1.0, 0.0, 1.0]));
+    }
+}
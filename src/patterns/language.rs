@@ -0,0 +1,389 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+
+use crate::{ProcessEvent, TrainingHistory};
+
+use super::{encode_morse, CharacterTable, EncodedCode, Symbol, TextMode};
+
+pub const BUILTIN_WORD_LISTS: [&str; 3] = ["en", "fr", "de"];
+
+pub const DEFAULT_COMPLETIONS_COUNT: usize = 3;
+/// Two slips of a finger to its neighbour
+pub const DEFAULT_MAX_CORRECTION_COST: f32 = 2.0;
+
+/// Substitution between letters typed with a different number of hand positions, or not in the table.
+/// It is also the highest cost of a substitution
+const UNRELATED_SUBSTITUTION_COST: f32 = 3.0;
+/// Confusions observed while training are considered as likely as half a finger slip
+const OBSERVED_CONFUSION_COST: f32 = 0.5;
+
+/// Lowercase words, the most frequent first
+#[derive(Debug, Clone)]
+pub struct WordList {
+    pub name: String,
+    words: Vec<String>,
+    known_words: HashSet<String>,
+}
+
+impl WordList {
+    pub fn builtin(name: &str) -> Option<Self> {
+        let content = match name {
+            "en" => include_str!("words/en.txt"),
+            "fr" => include_str!("words/fr.txt"),
+            "de" => include_str!("words/de.txt"),
+            _ => return None,
+        };
+
+        Some(Self::parse(name, content).expect("built in word lists are valid"))
+    }
+
+    /// One word per line, optionally followed by its count. Without counts the words
+    /// are expected from the most to the least frequent, lines starting with `#` are ignored
+    pub fn parse(name: &str, content: &str) -> anyhow::Result<Self> {
+        let mut words = vec![];
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let word = parts.next().unwrap_or_default().to_lowercase();
+            let count = match parts.next() {
+                Some(count) => count
+                    .parse::<u64>()
+                    .with_context(|| format!("line {}: invalid count {count:?}", i + 1))?,
+                None => 0,
+            };
+
+            words.push((word, count));
+        }
+
+        if words.is_empty() {
+            bail!("the word list is empty");
+        }
+
+        // Stable, words without count keep their order
+        words.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        let mut known_words = HashSet::new();
+        let words: Vec<String> = words
+            .into_iter()
+            .map(|(word, _)| word)
+            .filter(|word| known_words.insert(word.clone()))
+            .collect();
+
+        Ok(Self {
+            name: name.to_string(),
+            words,
+            known_words,
+        })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to open word list {}", path.display()))?;
+
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Self::parse(&name, &content)
+            .with_context(|| format!("invalid word list {}", path.display()))
+    }
+
+    /// `name_or_path` is either a built in word list name or a file
+    pub fn load_or_builtin(name_or_path: &str) -> anyhow::Result<Self> {
+        match Self::builtin(name_or_path) {
+            Some(word_list) => Ok(word_list),
+            None => Self::load(Path::new(name_or_path)),
+        }
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.known_words.contains(&word.to_lowercase())
+    }
+
+    /// The most frequent words starting with `prefix`
+    pub fn completions(&self, prefix: &str, count: usize) -> Vec<String> {
+        let prefix = prefix.to_lowercase();
+
+        self.words
+            .iter()
+            .filter(|word| word.len() > prefix.len() && word.starts_with(&prefix))
+            .take(count)
+            .cloned()
+            .collect()
+    }
+}
+
+/// How a letter is typed in the text mode of the model
+#[derive(Debug, Clone)]
+enum LetterCode {
+    /// Finger of each hand position
+    Positions(Vec<usize>),
+    /// Fingers flexed together, the thumb being the lowest bit
+    Chord(u8),
+    /// Dots and dashes
+    Morse(&'static str),
+}
+
+impl LetterCode {
+    fn distance(&self, other: &Self) -> f32 {
+        let distance = match (self, other) {
+            (Self::Positions(a), Self::Positions(b)) if a.len() == b.len() => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| match a.abs_diff(*b) {
+                    0 => 0.0,
+                    1 => 1.0,
+                    _ => 2.0,
+                })
+                .sum(),
+            (Self::Chord(a), Self::Chord(b)) => (a ^ b).count_ones() as f32,
+            (Self::Morse(a), Self::Morse(b)) => edit_distance(a.as_bytes(), b.as_bytes()) as f32,
+            _ => UNRELATED_SUBSTITUTION_COST,
+        };
+
+        distance.min(UNRELATED_SUBSTITUTION_COST)
+    }
+}
+
+/// How likely a letter is typed instead of another, from the way the text mode types them.
+/// With hand positions, a finger slipping to its neighbour costs 1, to another finger 2.
+/// With chords, each finger added or missing costs 1.
+/// With Morse code, each dot read as a dash, or the reverse, and each missed or extra flex costs 1
+#[derive(Debug, Clone)]
+pub struct ConfusionModel {
+    /// Code of each letter of the default layer, in lowercase
+    codes: HashMap<char, LetterCode>,
+    /// Intended and typed letters confused during training
+    observed: HashSet<(char, char)>,
+}
+
+impl ConfusionModel {
+    pub fn new(character_table: &CharacterTable, text_mode: &TextMode) -> Self {
+        let mut codes = HashMap::new();
+
+        for (position, symbol) in character_table.layers[0].symbols.iter().enumerate() {
+            let Symbol::Text(text) = symbol else {
+                continue;
+            };
+
+            let mut chars = text.chars();
+            let (Some(letter), None) = (chars.next(), chars.next()) else {
+                continue;
+            };
+            if !letter.is_alphabetic() {
+                continue;
+            }
+
+            let code = position as u8 + 1;
+            let letter_code = match text_mode {
+                TextMode::Positions => LetterCode::Positions(EncodedCode::new(code).fingers),
+                TextMode::Chord(_) => LetterCode::Chord(code),
+                TextMode::Morse(_) => match encode_morse(&letter.to_uppercase().to_string()) {
+                    Some(signals) => LetterCode::Morse(signals),
+                    None => continue,
+                },
+            };
+            codes.insert(to_lowercase(letter), letter_code);
+        }
+
+        Self {
+            codes,
+            observed: HashSet::new(),
+        }
+    }
+
+    /// Makes the confusions of the participant's training sessions more likely
+    pub fn add_training_history(&mut self, history: &TrainingHistory) {
+        let confusions = history
+            .sessions
+            .iter()
+            .flat_map(|session| &session.confusions);
+
+        for confusion in confusions {
+            let mut expected = confusion.expected.chars();
+            let mut typed = confusion.typed.chars();
+
+            if let (Some(expected), None, Some(typed), None) =
+                (expected.next(), expected.next(), typed.next(), typed.next())
+            {
+                self.observed
+                    .insert((to_lowercase(expected), to_lowercase(typed)));
+            }
+        }
+    }
+
+    pub fn substitution_cost(&self, intended: char, typed: char) -> f32 {
+        let (intended, typed) = (to_lowercase(intended), to_lowercase(typed));
+        if intended == typed {
+            return 0.0;
+        }
+
+        let cost = match (self.codes.get(&intended), self.codes.get(&typed)) {
+            (Some(intended), Some(typed)) => intended.distance(typed),
+            _ => UNRELATED_SUBSTITUTION_COST,
+        };
+
+        if self.observed.contains(&(intended, typed)) {
+            return cost.min(OBSERVED_CONFUSION_COST);
+        }
+
+        cost
+    }
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+fn to_lowercase(letter: char) -> char {
+    letter.to_lowercase().next().unwrap_or(letter)
+}
+
+/// Follows the characters typed with `TextPattern` to propose completions of the current word
+/// and correct finished words, assuming the participant only typed wrong letters
+pub struct LanguageLayer {
+    word_list: WordList,
+    confusion_model: ConfusionModel,
+
+    max_correction_cost: f32,
+    completions_count: usize,
+
+    current_word: String,
+    reported_completions: Vec<String>,
+}
+
+impl LanguageLayer {
+    pub fn new(word_list: WordList, confusion_model: ConfusionModel) -> Self {
+        Self {
+            word_list,
+            confusion_model,
+
+            max_correction_cost: DEFAULT_MAX_CORRECTION_COST,
+            completions_count: DEFAULT_COMPLETIONS_COUNT,
+
+            current_word: String::new(),
+            reported_completions: vec![],
+        }
+    }
+
+    pub fn max_correction_cost(&mut self, max_correction_cost: f32) {
+        self.max_correction_cost = max_correction_cost;
+    }
+
+    pub fn completions_count(&mut self, completions_count: usize) {
+        self.completions_count = completions_count;
+    }
+
+    pub fn reset(&mut self) {
+        self.current_word.clear();
+        self.reported_completions.clear();
+    }
+
+    /// Takes a character typed by `TextPattern`, anything but letters ends the current word
+    pub fn push(&mut self, text: &str) -> Vec<ProcessEvent> {
+        let mut events = vec![];
+
+        if text == "\u{8}" {
+            self.current_word.pop();
+        } else if !text.is_empty() && text.chars().all(char::is_alphabetic) {
+            self.current_word.push_str(text);
+        } else if !self.current_word.is_empty() {
+            let raw = std::mem::take(&mut self.current_word);
+            events.push(ProcessEvent::WordDecoded {
+                corrected: self.correct(&raw),
+                raw,
+            });
+        }
+
+        let completions = match self.current_word.is_empty() {
+            true => vec![],
+            false => self.completions(&self.current_word),
+        };
+
+        if completions != self.reported_completions {
+            self.reported_completions = completions.clone();
+            events.push(ProcessEvent::WordCompletions {
+                prefix: self.current_word.clone(),
+                completions,
+            });
+        }
+
+        events
+    }
+
+    fn completions(&self, prefix: &str) -> Vec<String> {
+        self.word_list
+            .completions(prefix, self.completions_count)
+            .into_iter()
+            .map(|word| match_case(&word, prefix))
+            .collect()
+    }
+
+    /// The most frequent word of the same length reachable with the lowest substitution cost
+    pub fn correct(&self, raw: &str) -> String {
+        if self.word_list.contains(raw) {
+            return raw.to_string();
+        }
+
+        let raw_letters: Vec<char> = raw.chars().collect();
+        let mut best: Option<(f32, &String)> = None;
+
+        for word in self.word_list.words() {
+            if word.chars().count() != raw_letters.len() {
+                continue;
+            }
+
+            let cost: f32 = word
+                .chars()
+                .zip(&raw_letters)
+                .map(|(intended, typed)| self.confusion_model.substitution_cost(intended, *typed))
+                .sum();
+
+            if cost <= self.max_correction_cost
+                && best.is_none_or(|(best_cost, _)| cost < best_cost)
+            {
+                best = Some((cost, word));
+            }
+        }
+
+        match best {
+            Some((_, word)) => match_case(word, raw),
+            None => raw.to_string(),
+        }
+    }
+}
+
+/// Character tables type uppercase letters
+fn match_case(word: &str, typed: &str) -> String {
+    if typed.chars().any(char::is_lowercase) {
+        return word.to_string();
+    }
+
+    word.to_uppercase()
+}
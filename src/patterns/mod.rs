@@ -1,12 +1,54 @@
+use anyhow::bail;
 use chrono::{DateTime, Local};
 
+mod actions;
+mod arming;
+mod chord;
+mod config;
+mod encoder;
+mod engine;
+mod evidence;
+mod framing;
+mod gesture;
+mod language;
+mod morse;
+mod table;
 mod text;
-pub use text::TextPattern;
+pub use actions::{ActionRunner, PatternAction};
+pub use arming::{DecoderArming, DEFAULT_ARMING_REPETITIONS, DEFAULT_ARMING_WINDOW_MS};
+pub use chord::{chord_code, ChordDecoder, FULL_HAND_CHORD_CODE};
+pub use config::{PatternConfig, PatternDefinition, StepDefinition};
+pub use encoder::{EncodedCode, EncodedSymbol, EncoderTiming, FingerPress, TextEncoder};
+pub use engine::{PatternDetection, PatternEngine, PatternProgress, PatternSpec};
+pub use evidence::{
+    chord_code_probabilities, position_code_probabilities, FingerEvidence,
+    DEFAULT_EVIDENCE_WINDOW_MS,
+};
+pub use framing::{
+    MessageFraming, MessageFramingConfig, MessageIssue, ReceivedMessage,
+    DEFAULT_MESSAGE_MIN_CONFIDENCE, DEFAULT_MESSAGE_TIMEOUT_MS,
+};
+pub use gesture::{
+    GestureMatch, GestureRecognizer, GestureTemplate, DEFAULT_GESTURE_MIN_SIMILARITY,
+};
+pub use language::{
+    ConfusionModel, LanguageLayer, WordList, BUILTIN_WORD_LISTS, DEFAULT_COMPLETIONS_COUNT,
+    DEFAULT_MAX_CORRECTION_COST,
+};
+pub use morse::{decode_morse, encode_morse, MorseConfig, MorseDecoder, MorseOutput, MorseReading};
+pub use table::{
+    CharacterLayer, CharacterTable, Symbol, BUILTIN_CHARACTER_TABLES, MAX_CHARACTER_CODE,
+};
+pub use text::{
+    CharacterCandidate, TextMode, TextPattern, TextProgress, TypedCharacter,
+    DEFAULT_TEXT_MAX_DELAY, MAX_CHARACTER_ALTERNATIVES,
+};
 
 pub const FINGERS_ORDER: [u8; 5] = [0, 1, 2, 3, 4];
 
 pub const DEFAULT_PATTERN_MAX_DELAY: u32 = 500;
 pub const DEFAULT_REPEATING_PATTERN_DELAY: u32 = 1000;
+pub const DEFAULT_CHORD_SETTLE_MS: u32 = 150;
 
 pub struct ReapeatingPattern {
     pattern: Pattern,
@@ -15,6 +57,8 @@ pub struct ReapeatingPattern {
 
     max_ms_delay: u32,
     last_time_done: DateTime<Local>,
+    /// When the first repetition started
+    started_at: Option<DateTime<Local>>,
 }
 
 impl ReapeatingPattern {
@@ -25,10 +69,20 @@ impl ReapeatingPattern {
 
             max_ms_delay,
             last_time_done: chrono::Local::now(),
+            started_at: None,
         }
     }
 
     pub fn process_moved_fingers(&mut self, moved_fingers: &[bool; 5], time: DateTime<Local>) {
+        self.process(moved_fingers, &[], time);
+    }
+
+    pub fn process(
+        &mut self,
+        moved_fingers: &[bool; 5],
+        gestures: &[GestureMatch],
+        time: DateTime<Local>,
+    ) {
         let elapsed_time = time
             .signed_duration_since(self.last_time_done)
             .num_milliseconds() as u32;
@@ -37,61 +91,253 @@ impl ReapeatingPattern {
             self.nb_done = 0;
         }
 
-        self.pattern.process_moved_fingers(moved_fingers, time);
+        self.pattern.process(moved_fingers, gestures, time);
 
         if self.pattern.is_done() {
+            if self.nb_done == 0 {
+                self.started_at = self.pattern.started_at();
+            }
+
             self.nb_done += 1;
             self.last_time_done = time;
             self.pattern.reset();
         }
     }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// When the participant started the first of the repetitions performed so far
+    pub fn started_at(&self) -> Option<DateTime<Local>> {
+        (self.nb_done > 0).then_some(self.started_at).flatten()
+    }
+
+    /// Time left to perform the next step, or the next repetition, before everything is reset
+    pub fn remaining_ms(&self, time: DateTime<Local>) -> Option<i64> {
+        if let Some(remaining_ms) = self.pattern.remaining_ms(time) {
+            return Some(remaining_ms);
+        }
+
+        (self.nb_done > 0)
+            .then(|| (self.max_ms_delay as i64 - elapsed_ms(self.last_time_done, time)).max(0))
+    }
+
+    pub fn reset(&mut self) {
+        self.nb_done = 0;
+        self.pattern.reset();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepKind {
+    /// The fingers start moving together and stay so for at least `min_hold_ms`
+    Press { min_hold_ms: u32 },
+    /// The fingers stop moving, all of them when the step has no finger
+    Release,
+    /// The gesture template with this name is recognized, the fingers are not looked at
+    Gesture { name: String },
+}
+
+/// Fingers that must move together, and how long after the previous step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternStep {
+    pub fingers: [bool; 5],
+    pub kind: StepKind,
+    pub max_ms_delay: u32,
+}
+
+impl PatternStep {
+    pub fn finger(finger: u8, max_ms_delay: u32) -> anyhow::Result<Self> {
+        if finger > 4 {
+            bail!("invalid finger {finger}, expected 0 to 4");
+        }
+
+        let mut fingers = [false; 5];
+        fingers[finger as usize] = true;
+
+        Ok(Self {
+            fingers,
+            kind: StepKind::Press { min_hold_ms: 0 },
+            max_ms_delay,
+        })
+    }
 }
 
 pub struct Pattern {
-    fingers_order: Vec<u8>,
+    steps: Vec<PatternStep>,
     current_index: usize,
+    last_moved_fingers: [bool; 5],
+
+    chord_settle_ms: u32,
+    chord_started_at: Option<DateTime<Local>>,
+    held_since: Option<DateTime<Local>>,
+    /// When the fingers of the first step started moving
+    started_at: Option<DateTime<Local>>,
 
-    max_ms_delay: u32,
     last_finger_time: DateTime<Local>,
 }
 
 impl Pattern {
-    pub fn new(fingers_order: Vec<u8>, max_ms_delay: u32) -> Self {
+    pub fn new(fingers_order: Vec<u8>, max_ms_delay: u32) -> anyhow::Result<Self> {
+        Ok(Self::from_steps(
+            fingers_order
+                .into_iter()
+                .map(|finger| PatternStep::finger(finger, max_ms_delay))
+                .collect::<anyhow::Result<_>>()?,
+        ))
+    }
+
+    pub fn from_steps(steps: Vec<PatternStep>) -> Self {
         Self {
-            fingers_order,
-            max_ms_delay,
+            steps,
 
             current_index: 0,
+            last_moved_fingers: [false; 5],
+
+            chord_settle_ms: DEFAULT_CHORD_SETTLE_MS,
+            chord_started_at: None,
+            held_since: None,
+            started_at: None,
+
             last_finger_time: chrono::Local::now(),
         }
     }
 
-    pub fn process_moved_fingers(&mut self, moved_fingers: &[bool; 5], time: DateTime<Local>) {
+    /// The fingers of a chord can start moving this long apart
+    pub fn chord_settle_ms(&mut self, chord_settle_ms: u32) {
+        self.chord_settle_ms = chord_settle_ms;
+    }
+
+    /// `gestures` are the gestures recognized at the same time as the moving fingers
+    pub fn process(
+        &mut self,
+        moved_fingers: &[bool; 5],
+        gestures: &[GestureMatch],
+        time: DateTime<Local>,
+    ) {
+        if self.is_done() {
+            return;
+        }
+
         let elapsed_time = time
             .signed_duration_since(self.last_finger_time)
             .num_milliseconds() as u32;
 
-        if elapsed_time > self.max_ms_delay {
-            self.current_index = 0;
+        // The delay is measured until the step starts, not until its hold is over
+        if self.current_index > 0
+            && self.held_since.is_none()
+            && elapsed_time > self.steps[self.current_index].max_ms_delay
+        {
+            self.reset();
         }
 
-        if !moved_fingers[self.fingers_order[self.current_index] as usize] {
-            return;
+        let step = self.steps[self.current_index].clone();
+        let is_step_done = match &step.kind {
+            StepKind::Press { min_hold_ms } => {
+                self.process_press(&step, *min_hold_ms, moved_fingers, time)
+            }
+            StepKind::Release => {
+                let all_fingers = !step.fingers.contains(&true);
+                (0..5).all(|i| !moved_fingers[i] || !(all_fingers || step.fingers[i]))
+            }
+            StepKind::Gesture { name } => gestures.iter().any(|gesture| gesture.name == *name),
+        };
+
+        self.last_moved_fingers = *moved_fingers;
+
+        if is_step_done {
+            if self.current_index == 0 {
+                self.started_at = Some(self.chord_started_at.unwrap_or(time));
+            }
+
+            self.current_index += 1;
+            self.last_finger_time = time;
+            self.chord_started_at = None;
+            self.held_since = None;
         }
+    }
 
-        if moved_fingers.iter().filter(|v| **v).count() > 1 {
-            return;
+    fn process_press(
+        &mut self,
+        step: &PatternStep,
+        min_hold_ms: u32,
+        moved_fingers: &[bool; 5],
+        time: DateTime<Local>,
+    ) -> bool {
+        if let Some(held_since) = self.held_since {
+            if *moved_fingers != step.fingers {
+                // Released too early or another finger joined
+                self.held_since = None;
+                return false;
+            }
+
+            return elapsed_ms(held_since, time) >= min_hold_ms as i64;
+        }
+
+        let has_other_finger = (0..5).any(|i| moved_fingers[i] && !step.fingers[i]);
+        if has_other_finger || !moved_fingers.contains(&true) {
+            self.chord_started_at = None;
+            return false;
+        }
+
+        // A finger held over several notifications only counts once
+        let is_new_movement =
+            (0..5).any(|i| step.fingers[i] && moved_fingers[i] && !self.last_moved_fingers[i]);
+
+        if is_new_movement && self.chord_started_at.is_none() {
+            self.chord_started_at = Some(time);
         }
 
-        self.current_index += 1;
-        self.last_finger_time = time;
+        if *moved_fingers != step.fingers || !is_new_movement {
+            return false;
+        }
+
+        let chord_started_at = self.chord_started_at.unwrap_or(time);
+        if elapsed_ms(chord_started_at, time) > self.chord_settle_ms as i64 {
+            return false;
+        }
+
+        self.held_since = Some(time);
+        min_hold_ms == 0
     }
 
     pub fn is_done(&self) -> bool {
-        self.current_index == self.fingers_order.len()
+        self.current_index == self.steps.len()
+    }
+
+    pub fn started_at(&self) -> Option<DateTime<Local>> {
+        (self.current_index > 0)
+            .then_some(self.started_at)
+            .flatten()
+    }
+
+    /// Index of the next step to perform
+    pub fn current_step(&self) -> usize {
+        self.current_index
+    }
+
+    pub fn steps_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Time left to start the next step before the pattern is reset, `None` before the first step
+    pub fn remaining_ms(&self, time: DateTime<Local>) -> Option<i64> {
+        if self.current_index == 0 || self.is_done() {
+            return None;
+        }
+
+        let max_ms_delay = self.steps[self.current_index].max_ms_delay as i64;
+        Some((max_ms_delay - elapsed_ms(self.last_finger_time, time)).max(0))
     }
 
     pub fn reset(&mut self) {
         self.current_index = 0;
+        self.chord_started_at = None;
+        self.held_since = None;
     }
 }
+
+fn elapsed_ms(since: DateTime<Local>, time: DateTime<Local>) -> i64 {
+    time.signed_duration_since(since).num_milliseconds()
+}
@@ -0,0 +1,434 @@
+use anyhow::bail;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// International Morse code, `.` being a dot and `-` a dash.
+/// The accented letters are only typed when the character table contains them
+const MORSE_CODE: [(&str, &str); 62] = [
+    (".-", "A"),
+    ("-...", "B"),
+    ("-.-.", "C"),
+    ("-..", "D"),
+    (".", "E"),
+    ("..-.", "F"),
+    ("--.", "G"),
+    ("....", "H"),
+    ("..", "I"),
+    (".---", "J"),
+    ("-.-", "K"),
+    (".-..", "L"),
+    ("--", "M"),
+    ("-.", "N"),
+    ("---", "O"),
+    (".--.", "P"),
+    ("--.-", "Q"),
+    (".-.", "R"),
+    ("...", "S"),
+    ("-", "T"),
+    ("..-", "U"),
+    ("...-", "V"),
+    (".--", "W"),
+    ("-..-", "X"),
+    ("-.--", "Y"),
+    ("--..", "Z"),
+    (".--.-", "À"),
+    (".-.-", "Ä"),
+    ("-.-..", "Ç"),
+    (".-..-", "È"),
+    ("..-..", "É"),
+    ("---.", "Ö"),
+    ("..--", "Ü"),
+    ("...--..", "ẞ"),
+    ("-----", "0"),
+    (".----", "1"),
+    ("..---", "2"),
+    ("...--", "3"),
+    ("....-", "4"),
+    (".....", "5"),
+    ("-....", "6"),
+    ("--...", "7"),
+    ("---..", "8"),
+    ("----.", "9"),
+    (".-.-.-", "."),
+    ("--..--", ","),
+    ("..--..", "?"),
+    (".----.", "'"),
+    ("-.-.--", "!"),
+    ("-..-.", "/"),
+    ("-.--.", "("),
+    ("-.--.-", ")"),
+    (".-...", "&"),
+    ("---...", ":"),
+    ("-.-.-.", ";"),
+    ("-...-", "="),
+    (".-.-.", "+"),
+    ("-....-", "-"),
+    ("..--.-", "_"),
+    (".-..-.", "\""),
+    (".--.-.", "@"),
+    // The error prosign erases the previous character
+    ("........", "\u{8}"),
+];
+
+/// The estimated unit stays within this ratio of the configured one
+const MAX_UNIT_DRIFT: f32 = 4.0;
+
+/// Slope of the dash probability around the two units boundary, on the log of the duration.
+/// A dot of one unit is read right with a probability of 0.98, a dash of three units of 0.92
+const DASH_PROBABILITY_STEEPNESS: f32 = 6.0;
+
+/// Dots and dashes of a character, and the probability that every flex was read right
+#[derive(Debug, Clone, PartialEq)]
+pub struct MorseReading {
+    pub signals: String,
+    pub probability: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MorseOutput {
+    /// The signals as read, followed by the other characters they give
+    /// when a single flex is read the other way
+    Character(Vec<MorseReading>),
+    /// A pause long enough to end the word
+    WordGap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MorseConfig {
+    /// Finger read, any finger when `None`
+    pub finger: Option<usize>,
+    /// Duration of a dot, a dash lasting three units.
+    /// A flex lasts as long as the finger is detected moving, which is shorter than the time it is
+    /// held once the aggregated value, the last value minus the mean, settles back
+    pub unit_ms: u32,
+    /// Pause after a flex ending the character, in units
+    pub letter_gap_units: f32,
+    /// Pause after a character typing a space, in units
+    pub word_gap_units: f32,
+    /// Weight of each flex in the estimation of the unit, 0 keeps it fixed
+    pub adaptation_rate: f32,
+}
+
+impl Default for MorseConfig {
+    fn default() -> Self {
+        Self {
+            finger: None,
+            unit_ms: 250,
+            letter_gap_units: 3.0,
+            word_gap_units: 7.0,
+            adaptation_rate: 0.2,
+        }
+    }
+}
+
+impl MorseConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(finger) = self.finger.filter(|finger| *finger > 4) {
+            bail!("invalid Morse finger {finger}, expected 0 to 4");
+        }
+
+        if self.unit_ms == 0 {
+            bail!("the Morse unit must last at least 1 ms");
+        }
+
+        if !self.letter_gap_units.is_finite() || self.letter_gap_units <= 0.0 {
+            bail!("the Morse letter gap must be positive");
+        }
+
+        if !self.word_gap_units.is_finite() || self.word_gap_units <= self.letter_gap_units {
+            bail!("the Morse word gap must be longer than the letter gap");
+        }
+
+        if !(0.0..=1.0).contains(&self.adaptation_rate) {
+            bail!("the Morse adaptation rate must be between 0 and 1");
+        }
+
+        Ok(())
+    }
+}
+
+/// Decodes the duration of the flexes of a single finger as Morse code.
+/// Flexes shorter than two units are dots, longer ones are dashes
+pub struct MorseDecoder {
+    config: MorseConfig,
+    /// Current estimation of the unit, following the speed of the participant
+    unit_ms: f32,
+
+    pressed_since: Option<DateTime<Local>>,
+    released_at: Option<DateTime<Local>>,
+    signals: String,
+    /// Probability of each signal being read right
+    signal_probabilities: Vec<f32>,
+    /// A space is typed after a pause once a character was typed
+    word_pending: bool,
+}
+
+impl MorseDecoder {
+    pub fn new(config: MorseConfig) -> Self {
+        Self {
+            unit_ms: config.unit_ms as f32,
+            config,
+
+            pressed_since: None,
+            released_at: None,
+            signals: String::new(),
+            signal_probabilities: vec![],
+            word_pending: false,
+        }
+    }
+
+    pub fn config(&self) -> &MorseConfig {
+        &self.config
+    }
+
+    pub fn unit_ms(&self) -> f32 {
+        self.unit_ms
+    }
+
+    /// Dots and dashes of the character being typed
+    pub fn signals(&self) -> &str {
+        &self.signals
+    }
+
+    /// Time left before the pending signals are decoded
+    pub fn remaining_ms(&self, time: DateTime<Local>) -> Option<i64> {
+        if self.signals.is_empty() || self.pressed_since.is_some() {
+            return None;
+        }
+
+        let released_at = self.released_at?;
+        let elapsed_ms = time.signed_duration_since(released_at).num_milliseconds();
+
+        Some((self.letter_gap_ms() as i64 - elapsed_ms).max(0))
+    }
+
+    pub fn reset(&mut self) {
+        self.pressed_since = None;
+        self.released_at = None;
+        self.signals.clear();
+        self.signal_probabilities.clear();
+        self.word_pending = false;
+    }
+
+    /// Returns the character or the word gap typed, if any
+    pub fn process(
+        &mut self,
+        moved_fingers: &[bool; 5],
+        time: DateTime<Local>,
+    ) -> Option<MorseOutput> {
+        let is_pressed = match self.config.finger {
+            Some(finger) => moved_fingers[finger],
+            None => moved_fingers.contains(&true),
+        };
+
+        match (is_pressed, self.pressed_since) {
+            (true, None) => {
+                self.pressed_since = Some(time);
+                None
+            }
+            (false, Some(pressed_since)) => {
+                let duration_ms = time.signed_duration_since(pressed_since).num_milliseconds();
+                self.push_flex(duration_ms as f32);
+                self.pressed_since = None;
+                self.released_at = Some(time);
+                None
+            }
+            (true, Some(_)) => None,
+            (false, None) => self.decode_after_pause(time),
+        }
+    }
+
+    fn push_flex(&mut self, duration_ms: f32) {
+        let is_dash = duration_ms >= 2.0 * self.unit_ms;
+        self.signals.push(if is_dash { '-' } else { '.' });
+
+        let boundary_ratio = duration_ms / (2.0 * self.unit_ms);
+        let dash_probability =
+            1.0 / (1.0 + (-DASH_PROBABILITY_STEEPNESS * boundary_ratio.ln()).exp());
+        self.signal_probabilities.push(if is_dash {
+            dash_probability
+        } else {
+            1.0 - dash_probability
+        });
+
+        let measured_unit_ms = if is_dash {
+            duration_ms / 3.0
+        } else {
+            duration_ms
+        };
+
+        let rate = self.config.adaptation_rate.clamp(0.0, 1.0);
+        let configured_unit_ms = self.config.unit_ms as f32;
+        self.unit_ms = (self.unit_ms * (1.0 - rate) + measured_unit_ms * rate).clamp(
+            configured_unit_ms / MAX_UNIT_DRIFT,
+            configured_unit_ms * MAX_UNIT_DRIFT,
+        );
+    }
+
+    fn decode_after_pause(&mut self, time: DateTime<Local>) -> Option<MorseOutput> {
+        let released_at = self.released_at?;
+        let elapsed_ms = time.signed_duration_since(released_at).num_milliseconds() as f32;
+
+        if !self.signals.is_empty() && elapsed_ms >= self.letter_gap_ms() {
+            let signals = std::mem::take(&mut self.signals);
+            let signal_probabilities = std::mem::take(&mut self.signal_probabilities);
+            let text = decode_morse(&signals)?;
+
+            // No space is typed after an erased character
+            self.word_pending = text != "\u{8}";
+            return Some(MorseOutput::Character(readings(
+                &signals,
+                &signal_probabilities,
+            )));
+        }
+
+        if self.word_pending && elapsed_ms >= self.unit_ms * self.config.word_gap_units {
+            self.word_pending = false;
+            return Some(MorseOutput::WordGap);
+        }
+
+        None
+    }
+
+    fn letter_gap_ms(&self) -> f32 {
+        self.unit_ms * self.config.letter_gap_units
+    }
+}
+
+/// The signals as read, then the valid codes with a single signal read the other way
+fn readings(signals: &str, signal_probabilities: &[f32]) -> Vec<MorseReading> {
+    let mut readings = vec![MorseReading {
+        signals: signals.to_string(),
+        probability: signal_probabilities.iter().product(),
+    }];
+
+    for (index, probability) in signal_probabilities.iter().enumerate() {
+        let flipped: String = signals
+            .chars()
+            .enumerate()
+            .map(|(i, signal)| match (i == index, signal) {
+                (true, '.') => '-',
+                (true, _) => '.',
+                (false, signal) => signal,
+            })
+            .collect();
+
+        if decode_morse(&flipped).is_none() {
+            continue;
+        }
+
+        let others: f32 = signal_probabilities
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != index)
+            .map(|(_, probability)| probability)
+            .product();
+
+        readings.push(MorseReading {
+            signals: flipped,
+            probability: (1.0 - probability) * others,
+        });
+    }
+
+    readings
+}
+
+/// Dots and dashes typing the text, `None` when it has no Morse code
+pub fn encode_morse(text: &str) -> Option<&'static str> {
+    MORSE_CODE
+        .iter()
+        .find(|(_, code_text)| *code_text == text)
+        .map(|(code, _)| *code)
+}
+
+pub fn decode_morse(signals: &str) -> Option<&'static str> {
+    MORSE_CODE
+        .iter()
+        .find(|(code, _)| *code == signals)
+        .map(|(_, text)| *text)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const RELEASED: [bool; 5] = [false; 5];
+    const INDEX: [bool; 5] = [false, true, false, false, false];
+
+    fn at(ms: i64) -> DateTime<Local> {
+        Local.timestamp_millis_opt(1_000_000 + ms).unwrap()
+    }
+
+    fn decoder(adaptation_rate: f32) -> MorseDecoder {
+        MorseDecoder::new(MorseConfig {
+            finger: Some(1),
+            unit_ms: 100,
+            letter_gap_units: 3.0,
+            word_gap_units: 7.0,
+            adaptation_rate,
+        })
+    }
+
+    fn flex(decoder: &mut MorseDecoder, from_ms: i64, to_ms: i64) {
+        assert_eq!(decoder.process(&INDEX, at(from_ms)), None);
+        assert_eq!(decoder.process(&RELEASED, at(to_ms)), None);
+    }
+
+    #[test]
+    fn unit_follows_the_flexes() {
+        let mut decoder = decoder(0.5);
+
+        flex(&mut decoder, 0, 100);
+        assert_eq!(decoder.signals(), ".");
+        assert_eq!(decoder.unit_ms(), 100.0);
+
+        // A dash measures three units
+        flex(&mut decoder, 200, 680);
+        assert_eq!(decoder.signals(), ".-");
+        assert_eq!(decoder.unit_ms(), 130.0);
+    }
+
+    #[test]
+    fn unit_stays_within_the_allowed_drift() {
+        let mut decoder = decoder(1.0);
+
+        flex(&mut decoder, 0, 3000);
+        assert_eq!(decoder.unit_ms(), 400.0);
+
+        flex(&mut decoder, 3100, 3110);
+        assert_eq!(decoder.unit_ms(), 25.0);
+    }
+
+    #[test]
+    fn fixed_unit_without_adaptation() {
+        let mut decoder = decoder(0.0);
+
+        flex(&mut decoder, 0, 600);
+        assert_eq!(decoder.unit_ms(), 100.0);
+    }
+
+    #[test]
+    fn decodes_after_the_adapted_letter_gap() {
+        let mut decoder = decoder(0.5);
+        flex(&mut decoder, 0, 100);
+        flex(&mut decoder, 200, 680);
+
+        // The letter gap is three units of 130 ms
+        assert_eq!(decoder.process(&RELEASED, at(1000)), None);
+        let Some(MorseOutput::Character(readings)) = decoder.process(&RELEASED, at(1070)) else {
+            panic!("expected a character");
+        };
+        assert_eq!(readings[0].signals, ".-");
+        assert_eq!(decode_morse(&readings[0].signals), Some("A"));
+        assert!(decoder.signals().is_empty());
+
+        assert_eq!(decoder.process(&RELEASED, at(1580)), None);
+        assert_eq!(
+            decoder.process(&RELEASED, at(1590)),
+            Some(MorseOutput::WordGap)
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+/// Highest code typed with two hand positions, `5 * 5 + 5`
+pub const MAX_CHARACTER_CODE: u8 = 30;
+
+pub const BUILTIN_CHARACTER_TABLES: [&str; 3] = ["en", "fr", "de"];
+
+/// What a code types in a layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Symbol {
+    /// Typed as is, an empty text leaves the code unassigned
+    Text(String),
+    /// Switches to another layer for the next character, or until switched again when `lock` is set
+    Layer {
+        layer: String,
+        #[serde(default)]
+        lock: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CharacterLayer {
+    pub name: String,
+    /// The symbol at position n is typed with the code n + 1
+    pub symbols: Vec<Symbol>,
+}
+
+/// Maps the codes typed with `TextPattern` to symbols, the first layer being the default one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CharacterTable {
+    pub name: String,
+    pub layers: Vec<CharacterLayer>,
+}
+
+impl Default for CharacterTable {
+    fn default() -> Self {
+        Self::builtin("en").expect("the en character table is built in")
+    }
+}
+
+impl CharacterTable {
+    pub fn builtin(name: &str) -> Option<Self> {
+        let content = match name {
+            "en" => include_str!("tables/en.toml"),
+            "fr" => include_str!("tables/fr.toml"),
+            "de" => include_str!("tables/de.toml"),
+            _ => return None,
+        };
+
+        Some(toml::from_str(content).expect("built in character tables are valid"))
+    }
+
+    /// Loads a table from a toml or json file, chosen from the extension like pattern configs
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to open character table {}", path.display()))?;
+
+        let table: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&content)
+                .with_context(|| format!("invalid character table {}", path.display()))?
+        } else {
+            toml::from_str(&content)
+                .with_context(|| format!("invalid character table {}", path.display()))?
+        };
+
+        table
+            .validate()
+            .with_context(|| format!("invalid character table {}", path.display()))?;
+
+        Ok(table)
+    }
+
+    /// `name_or_path` is either a built in table name or a file
+    pub fn load_or_builtin(name_or_path: &str) -> anyhow::Result<Self> {
+        match Self::builtin(name_or_path) {
+            Some(table) => Ok(table),
+            None => Self::load(Path::new(name_or_path)),
+        }
+    }
+
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    pub fn symbol(&self, layer: usize, code: u8) -> Option<&Symbol> {
+        self.layers
+            .get(layer)?
+            .symbols
+            .get((code as usize).checked_sub(1)?)
+    }
+
+    /// The text of the table typing `text`, regardless of its case
+    pub fn find_text(&self, text: &str) -> Option<&str> {
+        self.layers
+            .iter()
+            .flat_map(|layer| &layer.symbols)
+            .find_map(|symbol| match symbol {
+                Symbol::Text(symbol_text) if symbol_text.to_lowercase() == text.to_lowercase() => {
+                    Some(symbol_text.as_str())
+                }
+                _ => None,
+            })
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.layers.is_empty() {
+            bail!("the table must contain at least one layer");
+        }
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            let context = || format!("layers[{index}] \"{}\"", layer.name);
+
+            if self.layers[..index]
+                .iter()
+                .any(|other| other.name == layer.name)
+            {
+                bail!("{}: the name is already used", context());
+            }
+
+            if layer.symbols.len() > MAX_CHARACTER_CODE as usize {
+                bail!(
+                    "{}: symbols[{}] cannot be typed, codes go from 1 to {MAX_CHARACTER_CODE}",
+                    context(),
+                    MAX_CHARACTER_CODE
+                );
+            }
+
+            for (position, symbol) in layer.symbols.iter().enumerate() {
+                if let Symbol::Layer { layer: target, .. } = symbol {
+                    if self.layer_index(target).is_none() {
+                        bail!(
+                            "{}: symbols[{position}]: unknown layer \"{target}\"",
+                            context()
+                        );
+                    }
+                }
+            }
+        }
+
+        // Every layer must be usable from the default one,
+        // and the default layer must be restorable from every layer that can be locked
+        let (usable, _) = self.reachability(0);
+        if let Some((layer, _)) = self
+            .layers
+            .iter()
+            .zip(&usable)
+            .find(|(_, usable)| !**usable)
+        {
+            bail!(
+                "layer \"{}\" cannot be reached from the default layer",
+                layer.name
+            );
+        }
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            let can_be_locked = self.layers.iter().any(|other| {
+                other.symbols.iter().any(|symbol| match symbol {
+                    Symbol::Layer {
+                        layer: target,
+                        lock,
+                    } => *lock && *target == layer.name,
+                    Symbol::Text(_) => false,
+                })
+            });
+
+            if can_be_locked && !self.reachability(index).1[0] {
+                bail!(
+                    "layer \"{}\" can be locked but has no way back to the default layer",
+                    layer.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starting with `base` as the locked layer, returns the layers that can be used
+    /// and the layers that can become the locked one.
+    /// One-shot layers are left after a character, only locking changes the layer typing goes back to
+    fn reachability(&self, base: usize) -> (Vec<bool>, Vec<bool>) {
+        let mut usable = vec![false; self.layers.len()];
+        let mut bases = vec![false; self.layers.len()];
+        let mut bases_to_visit = vec![base];
+
+        while let Some(base) = bases_to_visit.pop() {
+            if bases[base] {
+                continue;
+            }
+            bases[base] = true;
+
+            let mut to_visit = vec![base];
+            let mut visited = vec![false; self.layers.len()];
+
+            while let Some(index) = to_visit.pop() {
+                if visited[index] {
+                    continue;
+                }
+                visited[index] = true;
+                usable[index] = true;
+
+                for symbol in &self.layers[index].symbols {
+                    let Symbol::Layer { layer, lock } = symbol else {
+                        continue;
+                    };
+                    let Some(target) = self.layer_index(layer) else {
+                        continue;
+                    };
+
+                    if *lock {
+                        bases_to_visit.push(target);
+                    } else {
+                        to_visit.push(target);
+                    }
+                }
+            }
+        }
+
+        (usable, bases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(layers: &[(&str, Vec<Symbol>)]) -> CharacterTable {
+        CharacterTable {
+            name: "test".to_string(),
+            layers: layers
+                .iter()
+                .map(|(name, symbols)| CharacterLayer {
+                    name: name.to_string(),
+                    symbols: symbols.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn text(text: &str) -> Symbol {
+        Symbol::Text(text.to_string())
+    }
+
+    fn layer(layer: &str, lock: bool) -> Symbol {
+        Symbol::Layer {
+            layer: layer.to_string(),
+            lock,
+        }
+    }
+
+    #[test]
+    fn builtin_tables_are_valid() {
+        for name in BUILTIN_CHARACTER_TABLES {
+            CharacterTable::builtin(name).unwrap().validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn one_shot_layer_is_reachable() {
+        let table = table(&[
+            ("letters", vec![text("A"), layer("numbers", false)]),
+            ("numbers", vec![text("1")]),
+        ]);
+
+        table.validate().unwrap();
+    }
+
+    #[test]
+    fn layer_not_reachable_from_the_default_one_is_rejected() {
+        let table = table(&[
+            ("letters", vec![text("A")]),
+            ("numbers", vec![text("1"), layer("letters", false)]),
+        ]);
+
+        let error = table.validate().unwrap_err().to_string();
+        assert!(error.contains("\"numbers\" cannot be reached"), "{error}");
+    }
+
+    #[test]
+    fn locked_layer_without_way_back_is_rejected() {
+        let table = table(&[
+            ("letters", vec![text("A"), layer("numbers", true)]),
+            ("numbers", vec![text("1")]),
+        ]);
+
+        let error = table.validate().unwrap_err().to_string();
+        assert!(error.contains("no way back"), "{error}");
+    }
+
+    #[test]
+    fn locked_layer_with_way_back_is_accepted() {
+        let table = table(&[
+            ("letters", vec![text("A"), layer("numbers", true)]),
+            ("numbers", vec![text("1"), layer("letters", true)]),
+        ]);
+
+        table.validate().unwrap();
+    }
+}
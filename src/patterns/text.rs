@@ -1,88 +1,452 @@
+use anyhow::Context;
 use chrono::{DateTime, Local};
 use enigo::{Enigo, Keyboard, Settings};
+use serde::Serialize;
 
-const NUMBER_MODE_VALUE: u8 = 27;
-const BACKSPACE_VALUE: u8 = 28;
-const DOT_VALUE: u8 = 29;
-const SPACE_VALUE: u8 = 30;
+use crate::{opt::FingersSensibility, parser::FingersFlexValues};
 
-const ALPHABET: [&str; 26] = [
-    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S",
-    "T", "U", "V", "W", "X", "Y", "Z",
-];
+use super::{
+    chord_code_probabilities, decode_morse, position_code_probabilities, CharacterTable,
+    ChordDecoder, FingerEvidence, MorseDecoder, MorseOutput, MorseReading, Symbol,
+    FULL_HAND_CHORD_CODE,
+};
 
-#[derive(PartialEq, Eq, Copy, Clone)]
-enum WritingMode {
-    Letters,
-    Numbers,
+/// How long after a single hand position it is applied alone
+pub const DEFAULT_TEXT_MAX_DELAY: u32 = 1000;
+
+/// Alternatives reported with a typed character, the most likely first
+pub const MAX_CHARACTER_ALTERNATIVES: usize = 3;
+const MIN_ALTERNATIVE_PROBABILITY: f32 = 0.01;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterCandidate {
+    pub text: String,
+    pub probability: f32,
+}
+
+/// A character typed with `TextPattern`, the confidence being known when finger evidence is tracked
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedCharacter {
+    pub text: String,
+    /// Probability of the typed character given the finger amplitudes, or the flex durations in Morse
+    pub confidence: Option<f32>,
+    pub alternatives: Vec<CharacterCandidate>,
+}
+
+impl TypedCharacter {
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            confidence: None,
+            alternatives: vec![],
+        }
+    }
+}
+
+/// Partial input of the current character
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextProgress {
+    /// First value of the character waiting for the second one, or the code of the chord
+    /// being typed in chord mode
+    pub pending_value: Option<u8>,
+    /// Layer the next character is typed in, `None` for the default layer
+    pub layer: Option<String>,
+    /// Dots and dashes of the character being typed in Morse mode
+    pub pending_signals: Option<String>,
+    /// Time left before the pending value, signals or chord are applied
+    pub remaining_ms: Option<i64>,
+}
+
+/// How the moved fingers are turned into characters
+pub enum TextMode {
+    /// Two successive hand positions give a code of the character table
+    Positions,
+    /// The duration of the flexes of a finger is read as Morse code
+    Morse(MorseDecoder),
+    /// The fingers flexed together give a code of the character table
+    Chord(ChordDecoder),
 }
 
 pub struct TextPattern {
+    mode: TextMode,
+
     last_hand: [bool; 5],
+    /// Set when reset, the fingers still moving must be released before decoding again
+    waiting_release: bool,
 
     current_value: Option<u8>,
-    current_mode: WritingMode,
+
+    finger_evidence: Option<FingerEvidence>,
+    /// Probabilities of each finger for the hand positions of the current character
+    position_probabilities: Vec<[f32; 5]>,
+
+    character_table: CharacterTable,
+    /// Layer the next character is typed in
+    current_layer: usize,
+    /// Layer typing goes back to after a character typed in a one-shot layer
+    locked_layer: usize,
 
     max_ms_delay: u32,
     last_moved_time: DateTime<Local>,
 
     on_char: Box<dyn Fn(&str) + Send + Sync>,
     use_keyboard_emulation: bool,
+    /// Characters typed since the last call to `take_typed`
+    typed: Vec<TypedCharacter>,
+
+    reported_progress: (Option<u8>, usize, Option<String>),
 }
 
 impl TextPattern {
     pub fn new(on_char: Box<dyn Fn(&str) + Send + Sync>) -> Self {
         Self {
+            mode: TextMode::Positions,
+
             last_hand: [false; 5],
+            waiting_release: false,
 
             current_value: None,
-            current_mode: WritingMode::Letters,
 
-            max_ms_delay: 1000,
+            finger_evidence: None,
+            position_probabilities: vec![],
+
+            character_table: CharacterTable::default(),
+            current_layer: 0,
+            locked_layer: 0,
+
+            max_ms_delay: DEFAULT_TEXT_MAX_DELAY,
             last_moved_time: Local::now(),
 
             on_char,
             use_keyboard_emulation: false,
+            typed: vec![],
+
+            reported_progress: (None, 0, None),
         }
     }
 
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+        self.reset();
+    }
+
     pub fn max_ms_delay(&mut self, max_ms_delay: u32) {
         self.max_ms_delay = max_ms_delay;
     }
 
+    /// The table must have been validated, see `CharacterTable::validate`
+    pub fn set_character_table(&mut self, character_table: CharacterTable) {
+        self.character_table = character_table;
+        self.reset();
+    }
+
+    /// Gives a confidence and alternatives to the characters typed in positions and chord modes
+    pub fn set_finger_evidence(&mut self, finger_evidence: Option<FingerEvidence>) {
+        self.finger_evidence = finger_evidence;
+    }
+
+    /// Keeps the finger evidence relative to the thresholds of the detection
+    pub fn set_sensibility(&mut self, sensibility: &FingersSensibility) {
+        if let Some(finger_evidence) = self.finger_evidence.as_mut() {
+            finger_evidence.set_sensibility(sensibility);
+        }
+    }
+
+    /// Feeds the finger evidence with the values the movements are detected from
+    pub fn push_values(&mut self, values: &FingersFlexValues, time: DateTime<Local>) {
+        if let Some(finger_evidence) = self.finger_evidence.as_mut() {
+            finger_evidence.push(values, time);
+        }
+    }
+
     pub fn use_keyboard_emulation(&mut self, use_keyboard_emulation: bool) {
         self.use_keyboard_emulation = use_keyboard_emulation;
     }
 
-    fn apply_value(&mut self, value: u8) {
-        if self.current_mode == WritingMode::Numbers {
-            if value == 30 {
-                self.apply_string_result("0");
-            } else {
-                self.apply_string_result(value.to_string().as_str());
+    pub fn progress(&self, time: DateTime<Local>) -> TextProgress {
+        if let TextMode::Morse(morse_decoder) = &self.mode {
+            return TextProgress {
+                pending_value: None,
+                layer: None,
+                pending_signals: self.pending_signals(),
+                remaining_ms: morse_decoder.remaining_ms(time),
+            };
+        }
+
+        if let TextMode::Chord(chord_decoder) = &self.mode {
+            return TextProgress {
+                pending_value: chord_decoder.pending_code(),
+                layer: self.current_layer_name(),
+                pending_signals: None,
+                remaining_ms: chord_decoder.remaining_ms(time),
+            };
+        }
+
+        TextProgress {
+            pending_value: self.current_value,
+            layer: self.current_layer_name(),
+            pending_signals: None,
+            remaining_ms: self.current_value.map(|_| {
+                let elapsed_time = time
+                    .signed_duration_since(self.last_moved_time)
+                    .num_milliseconds();
+
+                (self.max_ms_delay as i64 - elapsed_time).max(0)
+            }),
+        }
+    }
+
+    /// Returns the progress if it changed since the last call
+    pub fn take_progress_change(&mut self, time: DateTime<Local>) -> Option<TextProgress> {
+        let pending_value = match &self.mode {
+            TextMode::Chord(chord_decoder) => chord_decoder.pending_code(),
+            _ => self.current_value,
+        };
+        let progress = (pending_value, self.current_layer, self.pending_signals());
+        if progress == self.reported_progress {
+            return None;
+        }
+
+        self.reported_progress = progress;
+        Some(self.progress(time))
+    }
+
+    fn current_layer_name(&self) -> Option<String> {
+        (self.current_layer != 0)
+            .then(|| self.character_table.layers[self.current_layer].name.clone())
+    }
+
+    fn pending_signals(&self) -> Option<String> {
+        match &self.mode {
+            TextMode::Morse(morse_decoder) if !morse_decoder.signals().is_empty() => {
+                Some(morse_decoder.signals().to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the characters typed since the last call
+    pub fn take_typed(&mut self) -> Vec<TypedCharacter> {
+        std::mem::take(&mut self.typed)
+    }
+
+    /// Drops the partially entered character, e.g. when decoding is suspended.
+    /// Decoding starts again once every finger is released
+    pub fn reset(&mut self) {
+        self.last_hand = [false; 5];
+        self.waiting_release = true;
+        self.current_value = None;
+        self.position_probabilities.clear();
+        self.current_layer = 0;
+        self.locked_layer = 0;
+
+        match &mut self.mode {
+            TextMode::Positions => {}
+            TextMode::Morse(morse_decoder) => morse_decoder.reset(),
+            TextMode::Chord(chord_decoder) => chord_decoder.reset(),
+        }
+    }
+
+    /// `probabilities` of the codes that could have been typed, empty without finger evidence
+    fn apply_value(&mut self, value: u8, probabilities: &[(u8, f32)]) -> anyhow::Result<()> {
+        let symbol = self
+            .character_table
+            .symbol(self.current_layer, value)
+            .cloned();
+
+        let typed_layer = self.current_layer;
+        // One-shot layers only last for a character
+        self.current_layer = self.locked_layer;
+
+        match symbol {
+            Some(Symbol::Text(text)) if !text.is_empty() => {
+                let character = self.typed_character(text, value, typed_layer, probabilities);
+                self.apply_typed_character(character)?;
             }
+            Some(Symbol::Layer { layer, lock }) => {
+                let Some(index) = self.character_table.layer_index(&layer) else {
+                    return Ok(());
+                };
 
-            self.current_mode = WritingMode::Letters;
+                self.current_layer = index;
+                if lock {
+                    self.locked_layer = index;
+                }
+            }
+            _ => {}
         }
 
-        match value {
-            NUMBER_MODE_VALUE => self.current_mode = WritingMode::Numbers,
-            BACKSPACE_VALUE => self.apply_string_result("\x08"),
-            DOT_VALUE => self.apply_string_result("."),
-            SPACE_VALUE => self.apply_string_result(" "),
-            _ => self.apply_string_result(ALPHABET[value as usize - 1]),
+        Ok(())
+    }
+
+    fn typed_character(
+        &self,
+        text: String,
+        value: u8,
+        layer: usize,
+        probabilities: &[(u8, f32)],
+    ) -> TypedCharacter {
+        let confidence = probabilities
+            .iter()
+            .find(|(code, _)| *code == value)
+            .map(|(_, probability)| *probability);
+
+        let mut alternatives: Vec<CharacterCandidate> = probabilities
+            .iter()
+            .filter(|(code, probability)| {
+                *code != value && *probability >= MIN_ALTERNATIVE_PROBABILITY
+            })
+            .filter_map(
+                |(code, probability)| match self.character_table.symbol(layer, *code) {
+                    Some(Symbol::Text(text)) if !text.is_empty() => Some(CharacterCandidate {
+                        text: text.clone(),
+                        probability: *probability,
+                    }),
+                    _ => None,
+                },
+            )
+            .collect();
+
+        alternatives.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        alternatives.truncate(MAX_CHARACTER_ALTERNATIVES);
+
+        TypedCharacter {
+            text,
+            confidence,
+            alternatives,
         }
     }
 
-    pub fn apply_string_result(&mut self, result: &str) {
-        (self.on_char)(result);
+    /// Types the Morse character through the table, like the codes of the other modes,
+    /// the other readings of its flexes giving the alternatives
+    fn apply_morse_output(&mut self, output: MorseOutput) -> anyhow::Result<()> {
+        let readings = match output {
+            MorseOutput::Character(readings) => readings,
+            MorseOutput::WordGap => {
+                return match self.character_table.find_text(" ") {
+                    Some(text) => self.apply_typed_character(TypedCharacter::new(text.to_string())),
+                    None => Ok(()),
+                };
+            }
+        };
+
+        let candidate = |reading: &MorseReading| {
+            let text = self
+                .character_table
+                .find_text(decode_morse(&reading.signals)?)?;
+            Some(CharacterCandidate {
+                text: text.to_string(),
+                probability: reading.probability,
+            })
+        };
+
+        // The character as read is not typed when the table does not have it
+        let Some((reading, other_readings)) = readings.split_first() else {
+            return Ok(());
+        };
+        let Some(typed) = candidate(reading) else {
+            return Ok(());
+        };
+
+        let mut alternatives: Vec<CharacterCandidate> = other_readings
+            .iter()
+            .filter_map(candidate)
+            .filter(|alternative| {
+                alternative.text != typed.text
+                    && alternative.probability >= MIN_ALTERNATIVE_PROBABILITY
+            })
+            .collect();
+
+        alternatives.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        alternatives.truncate(MAX_CHARACTER_ALTERNATIVES);
+
+        self.apply_typed_character(TypedCharacter {
+            text: typed.text,
+            confidence: Some(typed.probability),
+            alternatives,
+        })
+    }
+
+    /// The character is decoded even when the keyboard emulation fails
+    fn apply_typed_character(&mut self, character: TypedCharacter) -> anyhow::Result<()> {
+        (self.on_char)(&character.text);
+        let text = character.text.to_lowercase();
+        self.typed.push(character);
+
         if self.use_keyboard_emulation {
-            let mut enigo = Enigo::new(&Settings::default()).unwrap();
-            let _ = enigo.text(&result.to_lowercase());
+            let mut enigo = Enigo::new(&Settings::default())
+                .context("unable to start the keyboard emulation")?;
+            enigo
+                .text(&text)
+                .with_context(|| format!("unable to type {text:?}"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Probabilities of each finger for a hand position detected now
+    fn push_position_probabilities(&mut self) {
+        if let Some(finger_evidence) = &self.finger_evidence {
+            self.position_probabilities
+                .push(finger_evidence.position_probabilities());
         }
     }
 
-    pub fn process_moved_fingers(&mut self, moved_fingers: &[bool; 5], time: DateTime<Local>) {
+    fn take_position_code_probabilities(&mut self) -> Vec<(u8, f32)> {
+        let probabilities = position_code_probabilities(&self.position_probabilities);
+        self.position_probabilities.clear();
+
+        probabilities
+    }
+
+    /// Fails when a character cannot be typed with the keyboard emulation
+    pub fn process_moved_fingers(
+        &mut self,
+        moved_fingers: &[bool; 5],
+        time: DateTime<Local>,
+    ) -> anyhow::Result<()> {
+        if self.waiting_release {
+            self.waiting_release = moved_fingers.contains(&true);
+            if self.waiting_release {
+                return Ok(());
+            }
+        }
+
+        match &mut self.mode {
+            TextMode::Positions => {}
+            TextMode::Morse(morse_decoder) => {
+                return match morse_decoder.process(moved_fingers, time) {
+                    Some(output) => self.apply_morse_output(output),
+                    None => Ok(()),
+                };
+            }
+            TextMode::Chord(chord_decoder) => {
+                match chord_decoder.process(moved_fingers, time) {
+                    Some(FULL_HAND_CHORD_CODE) => {
+                        self.current_layer = 0;
+                        self.locked_layer = 0;
+                    }
+                    Some(code) => {
+                        let probabilities = self
+                            .finger_evidence
+                            .as_ref()
+                            .map(|finger_evidence| {
+                                chord_code_probabilities(&finger_evidence.flex_probabilities())
+                            })
+                            .unwrap_or_default();
+
+                        self.apply_value(code, &probabilities)?;
+                    }
+                    None => {}
+                }
+
+                return Ok(());
+            }
+        }
+
         let new_hand_value = (self.last_hand != *moved_fingers)
             .then(|| compute_hand_value(moved_fingers))
             .flatten();
@@ -93,13 +457,16 @@ impl TextPattern {
             (Some(new_hand_value), None) => {
                 self.current_value = Some(new_hand_value);
                 self.last_moved_time = time;
+                self.push_position_probabilities();
             }
             (Some(new_hand_value), Some(current_value)) => {
                 let total_value = current_value * 5 + new_hand_value;
 
-                self.apply_value(total_value);
+                self.push_position_probabilities();
+                let probabilities = self.take_position_code_probabilities();
                 self.current_value = None;
                 self.last_moved_time = time;
+                self.apply_value(total_value, &probabilities)?;
             }
             (None, Some(current_value)) => {
                 let elapsed_time = time
@@ -107,12 +474,15 @@ impl TextPattern {
                     .num_milliseconds() as u32;
 
                 if elapsed_time > self.max_ms_delay {
-                    self.apply_value(current_value);
+                    let probabilities = self.take_position_code_probabilities();
                     self.current_value = None;
+                    self.apply_value(current_value, &probabilities)?;
                 }
             }
             (None, None) => {}
         }
+
+        Ok(())
     }
 }
 
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Local};
 use futures::StreamExt;
 
 #[cfg(feature = "lsl")]
@@ -7,14 +8,22 @@ use lsl::Pushable;
 use tokio::sync::Mutex;
 
 use crate::{
-    aggregator::MeanAggregator, opt::FingersSensibility, output::OutputRow,
-    FlexSensorGloveNotification, MovingFingers, OutputWriterDyn, TextPattern,
+    aggregator::MeanAggregator, opt::FingersSensibility, output::OutputRow, ActionRunner,
+    ActivityGate, ArtifactDetector, Calibration, CrosstalkCompensation, DecoderArming,
+    EpisodeExtractor, FlexSensorGloveNotification, GestureRecognizer, HysteresisDetector,
+    LanguageLayer, MessageFraming, MovementDetectorDyn, MovingFingers, OutputWriterDyn,
+    PatternAction, PatternDetection, PatternEngine, ProcessEvent, QuestionSession, Resampler,
+    SignalQualityMonitor, TextPattern, TimedEvent, TrainingSession, TranscriptWriter,
+    DEFAULT_MIN_ON_MS, DEFAULT_REFRACTORY_MS, DEFAULT_RELEASE_RATIO,
 };
 
 pub type NotificationFn = Box<dyn FnMut(&FlexSensorGloveNotification, MovingFingers) + Send + Sync>;
+pub type EventFn = Box<dyn FnMut(&TimedEvent) + Send + Sync>;
 
 pub struct Process<'a> {
-    fingers_sensibility: FingersSensibility,
+    movement_detector: MovementDetectorDyn,
+    resampler: Option<Resampler>,
+    crosstalk_compensation: Option<CrosstalkCompensation>,
 
     notification_stream: futures::stream::BoxStream<'a, crate::parser::FlexSensorGloveNotification>,
 
@@ -22,11 +31,29 @@ pub struct Process<'a> {
     output_writer: Arc<Mutex<Option<OutputWriterDyn>>>,
     raw_output_writer: Arc<Mutex<Option<csv::Writer<std::fs::File>>>>,
     text_pattern_detection: Arc<Mutex<Option<TextPattern>>>,
+    calibration: Arc<Mutex<Option<Calibration>>>,
+    artifact_detector: Arc<Mutex<Option<ArtifactDetector>>>,
+    signal_quality_monitor: Arc<Mutex<Option<SignalQualityMonitor>>>,
+    gesture_recognizer: Arc<Mutex<Option<GestureRecognizer>>>,
+    episode_extractor: Arc<Mutex<Option<EpisodeExtractor>>>,
+    activity_gate: Arc<Mutex<Option<ActivityGate>>>,
+    decoder_arming: Arc<Mutex<Option<DecoderArming>>>,
+    pattern_engine: Arc<Mutex<Option<PatternEngine>>>,
+    training_session: Arc<Mutex<Option<TrainingSession>>>,
+    question_session: Arc<Mutex<Option<QuestionSession>>>,
+    language_layer: Arc<Mutex<Option<LanguageLayer>>>,
+    message_framing: Arc<Mutex<Option<MessageFraming>>>,
+    transcript_writer: Arc<Mutex<Option<TranscriptWriter>>>,
+    /// Started with the first keyboard or command action
+    action_runner: Option<ActionRunner>,
 
     on_notification: Option<NotificationFn>,
+    on_event: Option<EventFn>,
 
     #[cfg(feature = "lsl")]
     lsl_stream_outlet: Option<lsl::StreamOutlet>,
+    #[cfg(feature = "lsl")]
+    lsl_marker_outlet: Option<lsl::StreamOutlet>,
 }
 
 impl<'a> Process<'a> {
@@ -38,18 +65,42 @@ impl<'a> Process<'a> {
         fingers_sensibility: FingersSensibility,
     ) -> Self {
         Self {
-            fingers_sensibility,
+            movement_detector: Box::new(HysteresisDetector::from_sensibility(
+                &fingers_sensibility,
+                DEFAULT_RELEASE_RATIO,
+                DEFAULT_MIN_ON_MS,
+                DEFAULT_REFRACTORY_MS,
+            )),
+            crosstalk_compensation: None,
+            resampler: None,
             notification_stream: notification_stream.boxed(),
 
             aggregator: Arc::new(Mutex::new(None)),
             output_writer: Arc::new(Mutex::new(None)),
             raw_output_writer: Arc::new(Mutex::new(None)),
             text_pattern_detection: Arc::new(Mutex::new(None)),
+            calibration: Arc::new(Mutex::new(None)),
+            artifact_detector: Arc::new(Mutex::new(None)),
+            signal_quality_monitor: Arc::new(Mutex::new(None)),
+            gesture_recognizer: Arc::new(Mutex::new(None)),
+            episode_extractor: Arc::new(Mutex::new(None)),
+            activity_gate: Arc::new(Mutex::new(None)),
+            decoder_arming: Arc::new(Mutex::new(None)),
+            pattern_engine: Arc::new(Mutex::new(None)),
+            training_session: Arc::new(Mutex::new(None)),
+            question_session: Arc::new(Mutex::new(None)),
+            language_layer: Arc::new(Mutex::new(None)),
+            message_framing: Arc::new(Mutex::new(None)),
+            transcript_writer: Arc::new(Mutex::new(None)),
+            action_runner: None,
 
             on_notification: None,
+            on_event: None,
 
             #[cfg(feature = "lsl")]
             lsl_stream_outlet: None,
+            #[cfg(feature = "lsl")]
+            lsl_marker_outlet: None,
         }
     }
 
@@ -64,6 +115,21 @@ impl<'a> Process<'a> {
         self.raw_output_writer = raw_output_writer;
     }
 
+    pub fn set_movement_detector(&mut self, movement_detector: MovementDetectorDyn) {
+        self.movement_detector = movement_detector;
+    }
+
+    pub fn set_resampler(&mut self, resampler: Option<Resampler>) {
+        self.resampler = resampler;
+    }
+
+    pub fn set_crosstalk_compensation(
+        &mut self,
+        crosstalk_compensation: Option<CrosstalkCompensation>,
+    ) {
+        self.crosstalk_compensation = crosstalk_compensation;
+    }
+
     pub fn set_aggregator(&mut self, aggregator: Arc<Mutex<Option<MeanAggregator>>>) {
         self.aggregator = aggregator;
     }
@@ -75,6 +141,67 @@ impl<'a> Process<'a> {
         self.text_pattern_detection = text_pattern_detection;
     }
 
+    pub fn set_calibration(&mut self, calibration: Arc<Mutex<Option<Calibration>>>) {
+        self.calibration = calibration;
+    }
+
+    pub fn set_artifact_detector(
+        &mut self,
+        artifact_detector: Arc<Mutex<Option<ArtifactDetector>>>,
+    ) {
+        self.artifact_detector = artifact_detector;
+    }
+
+    pub fn set_signal_quality_monitor(
+        &mut self,
+        signal_quality_monitor: Arc<Mutex<Option<SignalQualityMonitor>>>,
+    ) {
+        self.signal_quality_monitor = signal_quality_monitor;
+    }
+
+    pub fn set_gesture_recognizer(
+        &mut self,
+        gesture_recognizer: Arc<Mutex<Option<GestureRecognizer>>>,
+    ) {
+        self.gesture_recognizer = gesture_recognizer;
+    }
+
+    pub fn set_episode_extractor(
+        &mut self,
+        episode_extractor: Arc<Mutex<Option<EpisodeExtractor>>>,
+    ) {
+        self.episode_extractor = episode_extractor;
+    }
+
+    pub fn set_activity_gate(&mut self, activity_gate: Arc<Mutex<Option<ActivityGate>>>) {
+        self.activity_gate = activity_gate;
+    }
+
+    /// Without arming signal, text is always decoded
+    pub fn set_decoder_arming(&mut self, decoder_arming: Arc<Mutex<Option<DecoderArming>>>) {
+        self.decoder_arming = decoder_arming;
+    }
+
+    pub fn set_pattern_engine(&mut self, pattern_engine: Arc<Mutex<Option<PatternEngine>>>) {
+        self.pattern_engine = pattern_engine;
+    }
+
+    pub fn set_training_session(&mut self, training_session: Arc<Mutex<Option<TrainingSession>>>) {
+        self.training_session = training_session;
+    }
+
+    pub fn set_question_session(&mut self, question_session: Arc<Mutex<Option<QuestionSession>>>) {
+        self.question_session = question_session;
+    }
+
+    pub fn set_language_layer(&mut self, language_layer: Arc<Mutex<Option<LanguageLayer>>>) {
+        self.language_layer = language_layer;
+    }
+
+    pub fn set_message_framing(&mut self, message_framing: Arc<Mutex<Option<MessageFraming>>>) {
+        self.message_framing = message_framing;
+    }
+
     pub fn on_notification(
         &mut self,
         closure: impl FnMut(&FlexSensorGloveNotification, MovingFingers) + Send + Sync + 'static,
@@ -82,11 +209,28 @@ impl<'a> Process<'a> {
         self.on_notification = Some(Box::new(closure))
     }
 
+    pub fn set_transcript_writer(
+        &mut self,
+        transcript_writer: Arc<Mutex<Option<TranscriptWriter>>>,
+    ) {
+        self.transcript_writer = transcript_writer;
+    }
+
+    pub fn on_event(&mut self, closure: impl FnMut(&TimedEvent) + Send + Sync + 'static) {
+        self.on_event = Some(Box::new(closure))
+    }
+
     #[cfg(feature = "lsl")]
     pub fn set_lsl_stream_outlet(&mut self, lsl_stream_outlet: lsl::StreamOutlet) {
         self.lsl_stream_outlet = Some(lsl_stream_outlet);
     }
 
+    /// Events are pushed as json markers to this outlet
+    #[cfg(feature = "lsl")]
+    pub fn set_lsl_marker_outlet(&mut self, lsl_marker_outlet: lsl::StreamOutlet) {
+        self.lsl_marker_outlet = Some(lsl_marker_outlet);
+    }
+
     pub async fn run(&mut self) -> anyhow::Result<()> {
         while let Some(notification) = self.notification_stream.next().await {
             if let Some(raw_data_writer) = self.raw_output_writer.lock().await.as_mut() {
@@ -94,40 +238,494 @@ impl<'a> Process<'a> {
                 raw_data_writer.flush()?;
             }
 
-            let aggregated_notification =
-                if let Some(aggregator) = self.aggregator.lock().await.as_mut() {
-                    aggregator.push_and_aggregate(notification)
-                } else {
-                    notification
+            let Some(resampler) = self.resampler.as_mut() else {
+                self.process_notification(notification).await?;
+                continue;
+            };
+
+            let (samples, gap) = resampler.push(notification);
+
+            if let Some(gap) = gap {
+                let event = ProcessEvent::SignalGap {
+                    start: gap.start,
+                    duration_ms: (gap.end - gap.start).num_milliseconds(),
                 };
+                self.emit_event(TimedEvent::new(gap.end, event)).await?;
+            }
+
+            for sample in samples {
+                self.process_notification(sample).await?;
+            }
+        }
+
+        let episodes = match self.episode_extractor.lock().await.as_mut() {
+            Some(episode_extractor) => episode_extractor.finish(),
+            None => vec![],
+        };
+
+        for episode in episodes {
+            let event = TimedEvent::new(episode.end, ProcessEvent::MovementEpisode(episode));
+            self.emit_event(event).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn process_notification(
+        &mut self,
+        notification: FlexSensorGloveNotification,
+    ) -> anyhow::Result<()> {
+        let raw_flex_values = notification.flex_values;
+        let time = notification.dt;
+
+        let quality_changes = match self.signal_quality_monitor.lock().await.as_mut() {
+            Some(signal_quality_monitor) => signal_quality_monitor.process(&raw_flex_values),
+            None => vec![],
+        };
+
+        for (finger, quality) in quality_changes {
+            let event = ProcessEvent::ChannelQualityChanged { finger, quality };
+            self.emit_event(TimedEvent::new(time, event)).await?;
+        }
+
+        let aggregated_notification =
+            if let Some(aggregator) = self.aggregator.lock().await.as_mut() {
+                aggregator.push_and_aggregate(notification)
+            } else {
+                notification
+            };
+
+        let (is_calibrating, calibration_profile) = {
+            let mut calibration = self.calibration.lock().await;
+            match calibration.as_mut() {
+                Some(current_calibration) => {
+                    current_calibration.push(&raw_flex_values, &aggregated_notification);
+                    let profile = current_calibration.take_profile();
 
-            let moved_fingers = aggregated_notification
-                .flex_values
-                .detect_moved_fingers(&self.fingers_sensibility);
+                    if current_calibration.is_done() {
+                        *calibration = None;
+                    }
 
-            if let Some(on_notification) = self.on_notification.as_mut() {
-                on_notification(&aggregated_notification, moved_fingers)
+                    (true, profile)
+                }
+                None => (false, None),
             }
+        };
+
+        if let Some(profile) = calibration_profile {
+            self.apply_sensibility(&profile.fingers_sensibility).await;
+            self.crosstalk_compensation = profile.crosstalk_compensation()?;
+        }
+
+        if is_calibrating {
+            // The guided flexes must not leave a partial character behind once it ends
+            self.reset_text_decoding().await;
+        }
+
+        // Only detection uses the compensated values, the profile thresholds being calibrated on
+        // them. Calibration, the recording and the other steps keep the measured values
+        let detection_values = match &self.crosstalk_compensation {
+            Some(crosstalk_compensation) => {
+                crosstalk_compensation.apply(&aggregated_notification.flex_values)
+            }
+            None => aggregated_notification.flex_values,
+        };
+
+        let mut moved_fingers = self
+            .movement_detector
+            .detect(&detection_values, aggregated_notification.dt);
+
+        let (artifact_event, is_artifact) = match self.artifact_detector.lock().await.as_mut() {
+            Some(artifact_detector) => (
+                artifact_detector.process(
+                    &raw_flex_values,
+                    &aggregated_notification.flex_values,
+                    aggregated_notification.dt,
+                ),
+                artifact_detector.is_active(),
+            ),
+            None => (None, false),
+        };
+
+        if let Some(artifact_event) = artifact_event {
+            // A code pending before the movement would be committed once the artifact is over
+            if is_artifact {
+                self.reset_suspended_decoding().await;
+            }
+
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, artifact_event))
+                .await?;
+        }
+
+        if is_artifact {
+            moved_fingers = [false; 5];
+        }
+
+        if let Some(signal_quality_monitor) = self.signal_quality_monitor.lock().await.as_ref() {
+            signal_quality_monitor.mask(&mut moved_fingers);
+        }
+
+        if let Some(on_notification) = self.on_notification.as_mut() {
+            on_notification(&aggregated_notification, moved_fingers)
+        }
+
+        let output_row = OutputRow {
+            notification: &aggregated_notification,
+            moving_fingers: moved_fingers.map(|f| f as u32 * 500),
+        };
+
+        if let Some(output_writer) = self.output_writer.lock().await.as_mut() {
+            output_writer.write_row(&output_row)?;
+        }
+
+        let episodes = match self.episode_extractor.lock().await.as_mut() {
+            Some(episode_extractor) => episode_extractor.push(
+                &aggregated_notification.flex_values,
+                &moved_fingers,
+                aggregated_notification.dt,
+            ),
+            None => vec![],
+        };
+
+        for episode in episodes {
+            let event = ProcessEvent::MovementEpisode(episode);
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, event))
+                .await?;
+        }
 
-            let output_row = OutputRow {
-                notification: &aggregated_notification,
-                moving_fingers: moved_fingers.map(|f| f as u32 * 500),
+        // The gate compares the same compensated values as the detector,
+        // so that the movement of a neighbour finger does not count twice
+        let (gate_event, is_gate_active) = match self.activity_gate.lock().await.as_mut() {
+            Some(activity_gate) => (
+                activity_gate.process(
+                    &detection_values,
+                    &moved_fingers,
+                    aggregated_notification.dt,
+                ),
+                activity_gate.is_active(),
+            ),
+            None => (None, false),
+        };
+
+        if let Some(gate_event) = gate_event {
+            if is_gate_active {
+                self.reset_suspended_decoding().await;
+            }
+
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, gate_event))
+                .await?;
+        }
+
+        // The guided flexes of a calibration are not meant to type anything
+        let is_decoding_suspended = is_artifact || is_gate_active || is_calibrating;
+
+        let gesture_matches = match self.gesture_recognizer.lock().await.as_mut() {
+            Some(gesture_recognizer) if !is_decoding_suspended => {
+                gesture_recognizer.process(&aggregated_notification.flex_values)
+            }
+            _ => vec![],
+        };
+
+        for gesture_match in &gesture_matches {
+            let event = ProcessEvent::GestureDetected(gesture_match.clone());
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, event))
+                .await?;
+        }
+
+        let (pattern_detections, pattern_progress) = match self.pattern_engine.lock().await.as_mut()
+        {
+            Some(pattern_engine) if !is_decoding_suspended => (
+                pattern_engine.process(
+                    &moved_fingers,
+                    &gesture_matches,
+                    aggregated_notification.dt,
+                ),
+                pattern_engine.take_progress_changes(aggregated_notification.dt),
+            ),
+            _ => (vec![], vec![]),
+        };
+
+        for progress in pattern_progress {
+            let event = ProcessEvent::PatternProgress(progress);
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, event))
+                .await?;
+        }
+
+        // A pattern answers a question under the same conditions as the fingers
+        let is_answer_allowed = !is_decoding_suspended
+            && self
+                .decoder_arming
+                .lock()
+                .await
+                .as_ref()
+                .is_none_or(|decoder_arming| decoder_arming.is_armed());
+
+        for detection in pattern_detections {
+            self.handle_pattern_detection(detection, is_answer_allowed, aggregated_notification.dt)
+                .await?;
+        }
+
+        let action_failures = match self.action_runner.as_ref() {
+            Some(action_runner) => action_runner.take_failures(),
+            None => vec![],
+        };
+
+        for event in action_failures {
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, event))
+                .await?;
+        }
+
+        // The window still expires while decoding is suspended, but the signal is not looked for
+        let arming_fingers = if is_decoding_suspended {
+            [false; 5]
+        } else {
+            moved_fingers
+        };
+
+        let (arming_event, is_armed) = match self.decoder_arming.lock().await.as_mut() {
+            Some(decoder_arming) => (
+                decoder_arming.process(&arming_fingers, aggregated_notification.dt),
+                decoder_arming.is_armed(),
+            ),
+            None => (None, true),
+        };
+
+        if let Some(arming_event) = arming_event {
+            // The fingers of the arming signal, possibly still held,
+            // must not start a character, a pattern or an answer
+            self.reset_suspended_decoding().await;
+
+            if let Some(question_session) = self.question_session.lock().await.as_mut() {
+                question_session.suspend();
+            }
+
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, arming_event))
+                .await?;
+        }
+
+        // The fingers answering a question must not type anything
+        let is_answering = self
+            .question_session
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|question_session| !question_session.is_done());
+
+        if is_answering {
+            self.reset_text_decoding().await;
+        }
+
+        let (typed, text_progress) = match self.text_pattern_detection.lock().await.as_mut() {
+            Some(text_pattern) => {
+                if !is_decoding_suspended && is_armed && !is_answering {
+                    text_pattern.push_values(&detection_values, aggregated_notification.dt);
+                    text_pattern
+                        .process_moved_fingers(&moved_fingers, aggregated_notification.dt)?;
+                }
+
+                // Also reports the partial input dropped when decoding is suspended
+                (
+                    text_pattern.take_typed(),
+                    text_pattern.take_progress_change(aggregated_notification.dt),
+                )
+            }
+            None => (vec![], None),
+        };
+
+        if let Some(text_progress) = text_progress {
+            let event = ProcessEvent::TextProgress(text_progress);
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, event))
+                .await?;
+        }
+
+        let typed_texts: Vec<String> = typed.iter().map(|typed| typed.text.clone()).collect();
+
+        for character in typed {
+            if let Some(message_framing) = self.message_framing.lock().await.as_mut() {
+                message_framing.push(&character, aggregated_notification.dt);
+            }
+
+            let language_events = match self.language_layer.lock().await.as_mut() {
+                Some(language_layer) => language_layer.push(&character.text),
+                None => vec![],
             };
 
-            if let Some(output_writer) = self.output_writer.lock().await.as_mut() {
-                output_writer.write_row(&output_row)?;
+            let event = ProcessEvent::CharacterTyped(character);
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, event))
+                .await?;
+
+            for event in language_events {
+                self.emit_event(TimedEvent::new(aggregated_notification.dt, event))
+                    .await?;
             }
+        }
+
+        let message_event = match self.message_framing.lock().await.as_mut() {
+            Some(message_framing) => message_framing.process(aggregated_notification.dt),
+            None => None,
+        };
+
+        if let Some(message_event) = message_event {
+            self.emit_event(TimedEvent::new(aggregated_notification.dt, message_event))
+                .await?;
+        }
 
-            if let Some(text_pattern) = self.text_pattern_detection.lock().await.as_mut() {
-                text_pattern.process_moved_fingers(&moved_fingers, aggregated_notification.dt);
+        {
+            let mut training_session = self.training_session.lock().await;
+            if let Some(current_training_session) = training_session.as_mut() {
+                current_training_session.process(&typed_texts, aggregated_notification.dt);
+
+                if current_training_session.is_done() {
+                    *training_session = None;
+                }
             }
+        }
 
-            #[cfg(feature = "lsl")]
-            if let Some(lsl_stream_outlet) = &self.lsl_stream_outlet {
-                lsl_stream_outlet.push_sample(&output_row)?;
+        {
+            let mut question_session = self.question_session.lock().await;
+            if let Some(current_question_session) = question_session.as_mut() {
+                if !is_decoding_suspended && is_armed {
+                    current_question_session.process(
+                        &detection_values,
+                        &moved_fingers,
+                        aggregated_notification.dt,
+                    );
+                } else {
+                    current_question_session.suspend();
+                }
+
+                if current_question_session.is_done() {
+                    *question_session = None;
+                }
+            }
+        }
+
+        #[cfg(feature = "lsl")]
+        if let Some(lsl_stream_outlet) = &self.lsl_stream_outlet {
+            lsl_stream_outlet.push_sample(&output_row)?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_pattern_detection(
+        &mut self,
+        detection: PatternDetection,
+        is_answer_allowed: bool,
+        time: DateTime<Local>,
+    ) -> anyhow::Result<()> {
+        let event = ProcessEvent::PatternDetected {
+            name: detection.name.clone(),
+            repetitions: detection.repetitions,
+        };
+        self.emit_event(TimedEvent::new(time, event)).await?;
+
+        if is_answer_allowed {
+            let mut question_session = self.question_session.lock().await;
+            if let Some(current_question_session) = question_session.as_mut() {
+                current_question_session.pattern_detected(&detection.name, time);
+
+                if current_question_session.is_done() {
+                    *question_session = None;
+                }
+            }
+        }
+
+        let message_events = match self.message_framing.lock().await.as_mut() {
+            Some(message_framing) => {
+                message_framing.pattern_detected(&detection.name, detection.started_at, time)
+            }
+            None => vec![],
+        };
+
+        if !message_events.is_empty() {
+            // The fingers of the signal must not start a character of the message
+            self.reset_text_decoding().await;
+        }
+
+        for event in message_events {
+            self.emit_event(TimedEvent::new(time, event)).await?;
+        }
+
+        for action in detection.actions {
+            let event = match action {
+                PatternAction::Marker { label } => Some(ProcessEvent::Marker { label }),
+                PatternAction::Haptic { pulses_ms } => {
+                    Some(ProcessEvent::HapticRequested { pulses_ms })
+                }
+                // A failing action must not stop the recording, its failure is reported later
+                action => {
+                    self.action_runner
+                        .get_or_insert_with(ActionRunner::new)
+                        .run(&detection.name, action);
+                    None
+                }
+            };
+
+            if let Some(event) = event {
+                self.emit_event(TimedEvent::new(time, event)).await?;
             }
         }
 
         Ok(())
     }
+
+    /// Every threshold derived from the sensibility follows a new calibration
+    async fn apply_sensibility(&mut self, sensibility: &FingersSensibility) {
+        self.movement_detector.set_sensibility(sensibility);
+
+        if let Some(artifact_detector) = self.artifact_detector.lock().await.as_mut() {
+            artifact_detector.set_sensibility(sensibility);
+        }
+        if let Some(activity_gate) = self.activity_gate.lock().await.as_mut() {
+            activity_gate.set_sensibility(sensibility);
+        }
+        if let Some(text_pattern) = self.text_pattern_detection.lock().await.as_mut() {
+            text_pattern.set_sensibility(sensibility);
+        }
+        if let Some(question_session) = self.question_session.lock().await.as_mut() {
+            question_session.set_sensibility(sensibility);
+        }
+    }
+
+    /// Drops the partial characters and patterns when decoding gets suspended
+    async fn reset_suspended_decoding(&mut self) {
+        self.reset_text_decoding().await;
+
+        if let Some(pattern_engine) = self.pattern_engine.lock().await.as_mut() {
+            pattern_engine.reset();
+        }
+    }
+
+    /// Drops the partially entered character and word when decoding is suspended or interrupted
+    async fn reset_text_decoding(&mut self) {
+        if let Some(text_pattern) = self.text_pattern_detection.lock().await.as_mut() {
+            text_pattern.reset();
+        }
+
+        if let Some(language_layer) = self.language_layer.lock().await.as_mut() {
+            language_layer.reset();
+        }
+    }
+
+    async fn emit_event(&mut self, event: TimedEvent) -> anyhow::Result<()> {
+        if let Some(on_event) = self.on_event.as_mut() {
+            on_event(&event)
+        }
+
+        if let Some(output_writer) = self.output_writer.lock().await.as_mut() {
+            output_writer.write_event(&event)?;
+        }
+
+        if let Some(transcript_writer) = self.transcript_writer.lock().await.as_mut() {
+            transcript_writer.write_event(&event)?;
+        }
+
+        #[cfg(feature = "lsl")]
+        if let Some(lsl_marker_outlet) = &self.lsl_marker_outlet {
+            lsl_marker_outlet.push_sample(&vec![serde_json::to_string(&event)?])?;
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{crosstalk::CouplingMatrix, opt::FingersSensibility, CrosstalkCompensation};
+
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerCalibration {
+    /// Mean raw value of the sensor while the hand is at rest
+    pub baseline: u32,
+    /// Difference between the highest raw value reached while flexing and the baseline
+    pub range: u32,
+    /// Highest aggregated value observed while the hand is at rest, after crosstalk compensation
+    pub noise: u32,
+    /// Highest aggregated value observed while flexing the finger, after crosstalk compensation
+    pub peak: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GloveProfile {
+    pub name: String,
+    pub created_at: DateTime<Local>,
+    pub fingers: [FingerCalibration; 5],
+    pub fingers_sensibility: FingersSensibility,
+    /// Inter-finger coupling estimated from the single-finger flexes
+    #[serde(default)]
+    pub crosstalk: Option<CouplingMatrix>,
+}
+
+impl GloveProfile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("unable to open glove profile {}", path.display()))?;
+
+        serde_json::from_reader(file)
+            .with_context(|| format!("invalid glove profile {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("unable to create glove profile {}", path.display()))?;
+
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn crosstalk_compensation(&self) -> anyhow::Result<Option<CrosstalkCompensation>> {
+        self.crosstalk
+            .as_ref()
+            .map(CrosstalkCompensation::new)
+            .transpose()
+    }
+
+    /// The file name under which a profile is saved when only a folder is given
+    pub fn default_path(&self, folder: &Path) -> anyhow::Result<PathBuf> {
+        Self::validate_name(&self.name)?;
+
+        Ok(folder.join(format!("{}.json", self.name)))
+    }
+
+    /// The name is used as a file name, it cannot point to another folder
+    pub fn validate_name(name: &str) -> anyhow::Result<()> {
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            bail!("invalid profile name {name:?}, it must be a file name without path separators");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::{
+    artifact::ADC_MAX_VALUE,
+    parser::{FingersFlexValues, MovingFingers},
+};
+
+pub const DEFAULT_QUALITY_WINDOW_SIZE: usize = 50;
+pub const DEFAULT_FLATLINE_MAX_SPREAD: u32 = 2;
+pub const DEFAULT_NOISE_MAX: f32 = 50.0;
+pub const DEFAULT_JUMP_MAX: u32 = 1500;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChannelStatus {
+    Good,
+    /// The value barely changes, the sensor is most likely disconnected
+    Flatline,
+    /// The value is pinned at one end of the ADC range
+    Saturated,
+    Noisy,
+    /// The value changed more between two samples than a finger can physically produce
+    Jump,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SignalQualityConfig {
+    /// Number of raw samples the statuses are computed on
+    pub window_size: usize,
+    /// A channel is flat when the difference between its highest and lowest value is below this
+    pub flatline_max_spread: u32,
+    /// A channel is noisy when the mean absolute difference between consecutive samples is above this
+    pub noise_max: f32,
+    /// Largest plausible difference between two consecutive samples
+    pub jump_max: u32,
+    /// Bad channels are never reported as moving
+    pub mask_bad_channels: bool,
+}
+
+impl Default for SignalQualityConfig {
+    fn default() -> Self {
+        Self {
+            window_size: DEFAULT_QUALITY_WINDOW_SIZE,
+            flatline_max_spread: DEFAULT_FLATLINE_MAX_SPREAD,
+            noise_max: DEFAULT_NOISE_MAX,
+            jump_max: DEFAULT_JUMP_MAX,
+            mask_bad_channels: false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelQuality {
+    pub status: ChannelStatus,
+    /// From 0 (unusable) to 1 (clean signal)
+    pub quality_index: f32,
+}
+
+impl Default for ChannelQuality {
+    fn default() -> Self {
+        Self {
+            status: ChannelStatus::Good,
+            quality_index: 1.0,
+        }
+    }
+}
+
+/// Watches the raw values of each finger to detect broken or badly placed sensors
+pub struct SignalQualityMonitor {
+    config: SignalQualityConfig,
+    windows: [VecDeque<u32>; 5],
+    qualities: [ChannelQuality; 5],
+}
+
+impl SignalQualityMonitor {
+    pub fn new(config: SignalQualityConfig) -> Self {
+        assert!(config.window_size > 1);
+
+        Self {
+            config,
+            windows: Default::default(),
+            qualities: [ChannelQuality::default(); 5],
+        }
+    }
+
+    pub fn qualities(&self) -> &[ChannelQuality; 5] {
+        &self.qualities
+    }
+
+    /// Returns the fingers whose status changed with this sample
+    pub fn process(&mut self, raw_values: &FingersFlexValues) -> Vec<(usize, ChannelQuality)> {
+        let mut changes = vec![];
+
+        for (finger, window) in self.windows.iter_mut().enumerate() {
+            if window.len() >= self.config.window_size {
+                window.pop_front();
+            }
+            window.push_back(raw_values.0[finger]);
+
+            if window.len() < self.config.window_size {
+                continue;
+            }
+
+            let quality = compute_quality(window, &self.config);
+
+            if quality.status != self.qualities[finger].status {
+                changes.push((finger, quality));
+            }
+
+            self.qualities[finger] = quality;
+        }
+
+        changes
+    }
+
+    pub fn set_mask_bad_channels(&mut self, mask_bad_channels: bool) {
+        self.config.mask_bad_channels = mask_bad_channels;
+    }
+
+    pub fn mask(&self, moved_fingers: &mut MovingFingers) {
+        if !self.config.mask_bad_channels {
+            return;
+        }
+
+        for (moved, quality) in moved_fingers.iter_mut().zip(self.qualities.iter()) {
+            if quality.status != ChannelStatus::Good {
+                *moved = false;
+            }
+        }
+    }
+}
+
+fn compute_quality(window: &VecDeque<u32>, config: &SignalQualityConfig) -> ChannelQuality {
+    let saturated_count = window
+        .iter()
+        .filter(|&&value| value == 0 || value >= ADC_MAX_VALUE)
+        .count();
+
+    if saturated_count * 2 >= window.len() {
+        return ChannelQuality {
+            status: ChannelStatus::Saturated,
+            quality_index: 0.0,
+        };
+    }
+
+    let min = window.iter().min().copied().unwrap_or_default();
+    let max = window.iter().max().copied().unwrap_or_default();
+
+    if max - min <= config.flatline_max_spread {
+        return ChannelQuality {
+            status: ChannelStatus::Flatline,
+            quality_index: 0.0,
+        };
+    }
+
+    let differences: Vec<u32> = window
+        .iter()
+        .zip(window.iter().skip(1))
+        .map(|(a, b)| a.abs_diff(*b))
+        .collect();
+
+    let mean_difference = differences.iter().sum::<u32>() as f32 / differences.len() as f32;
+    let quality_index = (1.0 - mean_difference / config.noise_max).clamp(0.0, 1.0);
+
+    let status = if differences.iter().any(|&d| d > config.jump_max) {
+        ChannelStatus::Jump
+    } else if mean_difference > config.noise_max {
+        ChannelStatus::Noisy
+    } else {
+        ChannelStatus::Good
+    };
+
+    ChannelQuality {
+        status,
+        quality_index,
+    }
+}
@@ -0,0 +1,485 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    opt::FingersSensibility, parser::FingersFlexValues, FingerEvidence, PatternDefinition,
+    DEFAULT_EVIDENCE_WINDOW_MS, FINGER_NAMES,
+};
+
+/// Options of the questions without options
+pub const DEFAULT_QUESTION_OPTIONS: [&str; 3] = ["yes", "no", "unsure"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Question {
+    pub id: String,
+    pub text: String,
+    /// Yes, no and unsure when empty, e.g. `["A", "B", "C", "D", "E"]` for a multiple choice
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Name of the pattern giving each option, overriding the ones of the question list
+    #[serde(default)]
+    pub patterns: BTreeMap<String, String>,
+}
+
+/// Questions asked before sleep and answered in order during the dream. An option without
+/// pattern is given by flexing the finger at its index, the thumb for the first one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuestionList {
+    /// Name of the pattern giving an option, for every question having this option
+    #[serde(default)]
+    pub patterns: BTreeMap<String, String>,
+    pub questions: Vec<Question>,
+}
+
+impl QuestionList {
+    /// Toml file, or json when its extension is `json`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to open question list {}", path.display()))?;
+
+        let list: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&content)
+                .with_context(|| format!("invalid question list {}", path.display()))?
+        } else {
+            toml::from_str(&content)
+                .with_context(|| format!("invalid question list {}", path.display()))?
+        };
+
+        list.signals()
+            .with_context(|| format!("invalid question list {}", path.display()))?;
+
+        Ok(list)
+    }
+
+    /// The patterns giving answers must be among the patterns looked for
+    pub fn validate(&self, patterns: &[PatternDefinition]) -> anyhow::Result<()> {
+        for question in self.signals()? {
+            for (label, signal) in &question.options {
+                let AnswerSignal::Pattern(name) = signal else {
+                    continue;
+                };
+
+                let Some(pattern) = patterns.iter().find(|pattern| pattern.name == *name) else {
+                    bail!(
+                        "the pattern {name:?} of question {:?} is not defined",
+                        question.question.id
+                    );
+                };
+
+                // The finger flexed alone would answer before the pattern is completed
+                let first_finger = match pattern.steps.first() {
+                    Some(step) if !step.release && step.fingers.len() == 1 => {
+                        Some(step.fingers[0] as usize)
+                    }
+                    _ => None,
+                };
+
+                if let Some(finger) = first_finger.filter(|finger| {
+                    question
+                        .options
+                        .iter()
+                        .any(|(_, other)| *other == AnswerSignal::Finger(*finger))
+                }) {
+                    bail!(
+                        "option {label:?} of question {:?} starts with the {}, which gives another option",
+                        question.question.id,
+                        FINGER_NAMES[finger]
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signals(&self) -> anyhow::Result<Vec<AskedQuestion>> {
+        if self.questions.is_empty() {
+            bail!("a question list needs at least one question");
+        }
+
+        self.questions
+            .iter()
+            .map(|question| {
+                let labels: Vec<String> = match question.options.is_empty() {
+                    true => DEFAULT_QUESTION_OPTIONS.map(String::from).to_vec(),
+                    false => question.options.clone(),
+                };
+
+                let mut options: Vec<(String, AnswerSignal)> = vec![];
+                for (index, label) in labels.into_iter().enumerate() {
+                    if options.iter().any(|(other, _)| *other == label) {
+                        bail!("option {label:?} of question {:?} is repeated", question.id);
+                    }
+
+                    let pattern = question
+                        .patterns
+                        .get(&label)
+                        .or_else(|| self.patterns.get(&label));
+
+                    let signal = match pattern {
+                        Some(pattern) => AnswerSignal::Pattern(pattern.clone()),
+                        None if index < 5 => AnswerSignal::Finger(index),
+                        None => bail!(
+                            "option {label:?} of question {:?} needs a pattern, there are 5 fingers",
+                            question.id
+                        ),
+                    };
+
+                    options.push((label, signal));
+                }
+
+                Ok(AskedQuestion {
+                    question: question.clone(),
+                    options,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AnswerSignal {
+    Finger(usize),
+    Pattern(String),
+}
+
+impl AnswerSignal {
+    fn describe(&self) -> String {
+        match self {
+            AnswerSignal::Finger(finger) => FINGER_NAMES[*finger].to_string(),
+            AnswerSignal::Pattern(name) => format!("pattern {name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AskedQuestion {
+    question: Question,
+    options: Vec<(String, AnswerSignal)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionAnswer {
+    pub question_id: String,
+    pub question: String,
+    pub answer: String,
+    /// Finger or pattern the answer was given with
+    pub signal: String,
+    pub answered_at: DateTime<Local>,
+    /// Time since the question was asked or since the previous answer
+    pub latency_ms: i64,
+    /// Probability of the finger being the only one flexed, `None` for patterns
+    pub confidence: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionReport {
+    pub participant: String,
+    pub started_at: DateTime<Local>,
+    pub finished_at: DateTime<Local>,
+    pub answers: Vec<QuestionAnswer>,
+    /// Questions not answered before the session was stopped
+    pub unanswered: Vec<Question>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AnswerRow<'a> {
+    participant: &'a str,
+    question_id: &'a str,
+    question: &'a str,
+    answer: Option<&'a str>,
+    signal: Option<&'a str>,
+    answered_at: Option<DateTime<Local>>,
+    latency_ms: Option<i64>,
+    confidence: Option<f32>,
+}
+
+impl QuestionReport {
+    /// One row per question, the unanswered ones having no answer
+    pub fn write_csv(&self, path: &Path) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_path(path)
+            .with_context(|| format!("unable to create answers {}", path.display()))?;
+
+        for answer in &self.answers {
+            writer.serialize(AnswerRow {
+                participant: &self.participant,
+                question_id: &answer.question_id,
+                question: &answer.question,
+                answer: Some(&answer.answer),
+                signal: Some(&answer.signal),
+                answered_at: Some(answer.answered_at),
+                latency_ms: Some(answer.latency_ms),
+                confidence: answer.confidence,
+            })?;
+        }
+
+        for question in &self.unanswered {
+            writer.serialize(AnswerRow {
+                participant: &self.participant,
+                question_id: &question.id,
+                question: &question.text,
+                answer: None,
+                signal: None,
+                answered_at: None,
+                latency_ms: None,
+                confidence: None,
+            })?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum QuestionEvent {
+    QuestionAsked {
+        id: String,
+        text: String,
+        options: Vec<String>,
+        index: usize,
+        count: usize,
+    },
+    Answered(QuestionAnswer),
+    Finished(Box<QuestionReport>),
+}
+
+pub type QuestionEventFn = Box<dyn Fn(&QuestionEvent) + Send + Sync>;
+
+/// Waits for the answer to each question of a list, given with a finger or a pattern
+pub struct QuestionSession {
+    participant: String,
+
+    questions: Vec<AskedQuestion>,
+    current_question: usize,
+
+    started_at: Option<DateTime<Local>>,
+    asked_at: DateTime<Local>,
+    answers: Vec<QuestionAnswer>,
+
+    finger_evidence: Option<FingerEvidence>,
+    /// Finger flexed alone, the answer is given once it is released or its movement settled
+    pending_finger: Option<(usize, DateTime<Local>)>,
+    /// A new answer starts once every finger is released
+    waiting_release: bool,
+
+    on_event: Option<QuestionEventFn>,
+}
+
+impl QuestionSession {
+    pub fn new(participant: String, question_list: &QuestionList) -> anyhow::Result<Self> {
+        Ok(Self {
+            participant,
+
+            questions: question_list.signals()?,
+            current_question: 0,
+
+            started_at: None,
+            asked_at: Local::now(),
+            answers: vec![],
+
+            finger_evidence: None,
+            pending_finger: None,
+            waiting_release: false,
+
+            on_event: None,
+        })
+    }
+
+    /// Gives a confidence to the answers given with a finger
+    pub fn set_finger_evidence(&mut self, finger_evidence: Option<FingerEvidence>) {
+        self.finger_evidence = finger_evidence;
+    }
+
+    /// Keeps the finger evidence relative to the thresholds of the detection
+    pub fn set_sensibility(&mut self, sensibility: &FingersSensibility) {
+        if let Some(finger_evidence) = self.finger_evidence.as_mut() {
+            finger_evidence.set_sensibility(sensibility);
+        }
+    }
+
+    pub fn on_event(&mut self, closure: impl Fn(&QuestionEvent) + Send + Sync + 'static) {
+        self.on_event = Some(Box::new(closure))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.current_question >= self.questions.len()
+    }
+
+    /// Asks the first question on the first call, then looks for a finger answering the current one
+    pub fn process(
+        &mut self,
+        values: &FingersFlexValues,
+        moved_fingers: &[bool; 5],
+        time: DateTime<Local>,
+    ) {
+        if self.is_done() {
+            return;
+        }
+
+        self.start(time);
+
+        if let Some(finger_evidence) = self.finger_evidence.as_mut() {
+            finger_evidence.push(values, time);
+        }
+
+        if self.waiting_release {
+            self.waiting_release = moved_fingers.contains(&true);
+            return;
+        }
+
+        let moved: Vec<usize> = (0..5).filter(|i| moved_fingers[*i]).collect();
+
+        let Some((finger, since)) = self.pending_finger else {
+            if let [finger] = moved[..] {
+                self.pending_finger = Some((finger, time));
+            } else {
+                self.waiting_release = !moved.is_empty();
+            }
+            return;
+        };
+
+        if moved.iter().any(|other| *other != finger) {
+            // Several fingers flexed, the answer is ambiguous
+            self.pending_finger = None;
+            self.waiting_release = true;
+            return;
+        }
+
+        let elapsed_ms = time.signed_duration_since(since).num_milliseconds();
+        if !moved.is_empty() && elapsed_ms < DEFAULT_EVIDENCE_WINDOW_MS as i64 {
+            return;
+        }
+
+        self.pending_finger = None;
+        self.waiting_release = !moved.is_empty();
+
+        let confidence = self
+            .finger_evidence
+            .as_ref()
+            .map(|finger_evidence| finger_evidence.position_probabilities()[finger]);
+
+        self.answer(&AnswerSignal::Finger(finger), confidence, time);
+    }
+
+    /// Drops the answer being given, e.g. when decoding is suspended
+    pub fn suspend(&mut self) {
+        self.pending_finger = None;
+        self.waiting_release = true;
+
+        if let Some(finger_evidence) = self.finger_evidence.as_mut() {
+            finger_evidence.clear();
+        }
+    }
+
+    pub fn pattern_detected(&mut self, name: &str, time: DateTime<Local>) {
+        if self.is_done() || self.started_at.is_none() {
+            return;
+        }
+
+        self.answer(&AnswerSignal::Pattern(name.to_string()), None, time);
+    }
+
+    /// Ends the session before every question is answered
+    pub fn finish(&mut self, time: DateTime<Local>) {
+        if self.is_done() {
+            return;
+        }
+
+        self.current_question = self.questions.len();
+        self.emit_report(time);
+    }
+
+    fn start(&mut self, time: DateTime<Local>) {
+        if self.started_at.is_some() {
+            return;
+        }
+
+        self.started_at = Some(time);
+        self.ask_question(time);
+    }
+
+    fn answer(&mut self, signal: &AnswerSignal, confidence: Option<f32>, time: DateTime<Local>) {
+        let question = &self.questions[self.current_question];
+
+        // Signals not giving an option of the current question are ignored
+        let Some((label, _)) = question.options.iter().find(|(_, other)| other == signal) else {
+            return;
+        };
+
+        let answer = QuestionAnswer {
+            question_id: question.question.id.clone(),
+            question: question.question.text.clone(),
+            answer: label.clone(),
+            signal: signal.describe(),
+            answered_at: time,
+            latency_ms: time.signed_duration_since(self.asked_at).num_milliseconds(),
+            confidence,
+        };
+
+        self.emit(&QuestionEvent::Answered(answer.clone()));
+        self.answers.push(answer);
+        self.current_question += 1;
+
+        match self.is_done() {
+            true => self.emit_report(time),
+            false => self.ask_question(time),
+        }
+    }
+
+    fn ask_question(&mut self, time: DateTime<Local>) {
+        let question = &self.questions[self.current_question];
+
+        self.asked_at = time;
+        self.emit(&QuestionEvent::QuestionAsked {
+            id: question.question.id.clone(),
+            text: question.question.text.clone(),
+            options: question
+                .options
+                .iter()
+                .map(|(label, _)| label.clone())
+                .collect(),
+            index: self.current_question,
+            count: self.questions.len(),
+        });
+    }
+
+    fn emit_report(&mut self, time: DateTime<Local>) {
+        let answers = std::mem::take(&mut self.answers);
+        let unanswered = self.questions[answers.len()..]
+            .iter()
+            .map(|question| question.question.clone())
+            .collect();
+
+        let report = QuestionReport {
+            participant: self.participant.clone(),
+            started_at: self.started_at.unwrap_or(time),
+            finished_at: time,
+            answers,
+            unanswered,
+        };
+        self.emit(&QuestionEvent::Finished(Box::new(report)));
+    }
+
+    fn emit(&self, event: &QuestionEvent) {
+        if let Some(on_event) = self.on_event.as_ref() {
+            on_event(event)
+        }
+    }
+}
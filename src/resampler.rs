@@ -0,0 +1,213 @@
+use anyhow::bail;
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::parser::{FingersFlexValues, FlexSensorGloveNotification};
+
+pub const DEFAULT_RESAMPLE_RATE: f64 = 50.0;
+pub const DEFAULT_MAX_GAP_MS: u32 = 200;
+
+#[derive(Debug, Copy, Clone)]
+pub struct SignalGap {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+/// Fails when a model made from notifications resampled at `expected`
+/// is used on notifications resampled at `actual`, `None` meaning not resampled
+pub fn check_resample_rate(
+    what: &str,
+    expected: Option<f64>,
+    actual: Option<f64>,
+) -> anyhow::Result<()> {
+    let describe = |rate: Option<f64>| match rate {
+        Some(rate) => format!("a resample rate of {rate} Hz"),
+        None => "no resampling".to_string(),
+    };
+
+    if expected != actual {
+        bail!(
+            "the {what} expects {}, the notifications use {}",
+            describe(expected),
+            describe(actual)
+        );
+    }
+
+    Ok(())
+}
+
+/// Interpolates the notifications, delivered in bursts by BLE, onto a uniform time grid
+/// based on the device timestamps
+pub struct Resampler {
+    rate: f64,
+    period: TimeDelta,
+    max_gap: TimeDelta,
+
+    previous: Option<FlexSensorGloveNotification>,
+    next_time: Option<DateTime<Local>>,
+}
+
+impl Resampler {
+    pub fn new(rate: f64, max_gap_ms: u32) -> Self {
+        assert!(rate > 0.0);
+
+        Self {
+            rate,
+            period: TimeDelta::microseconds((1_000_000.0 / rate).round() as i64),
+            max_gap: TimeDelta::milliseconds(max_gap_ms as i64),
+
+            previous: None,
+            next_time: None,
+        }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Resamples a whole recording, the gaps too long to be filled restart the grid
+    pub fn resample(
+        &mut self,
+        recording: &[FlexSensorGloveNotification],
+    ) -> Vec<FlexSensorGloveNotification> {
+        recording
+            .iter()
+            .flat_map(|notification| self.push(notification.clone()).0)
+            .collect()
+    }
+
+    /// Returns the uniform samples up to the given notification,
+    /// and the gap preceding it when it is too long to be filled
+    pub fn push(
+        &mut self,
+        notification: FlexSensorGloveNotification,
+    ) -> (Vec<FlexSensorGloveNotification>, Option<SignalGap>) {
+        let (Some(previous), Some(mut next_time)) = (self.previous.as_ref(), self.next_time) else {
+            return (vec![self.restart_grid(notification)], None);
+        };
+
+        if notification.dt <= previous.dt {
+            // Duplicated or out of order notification
+            return (vec![], None);
+        }
+
+        if notification.dt - previous.dt > self.max_gap {
+            let gap = SignalGap {
+                start: previous.dt,
+                end: notification.dt,
+            };
+
+            return (vec![self.restart_grid(notification)], Some(gap));
+        }
+
+        let mut samples = vec![];
+        while next_time <= notification.dt {
+            samples.push(interpolate(previous, &notification, next_time));
+            next_time += self.period;
+        }
+
+        self.next_time = Some(next_time);
+        self.previous = Some(notification);
+
+        (samples, None)
+    }
+
+    fn restart_grid(
+        &mut self,
+        notification: FlexSensorGloveNotification,
+    ) -> FlexSensorGloveNotification {
+        self.next_time = Some(notification.dt + self.period);
+        self.previous = Some(notification.clone());
+
+        notification
+    }
+}
+
+fn interpolate(
+    from: &FlexSensorGloveNotification,
+    to: &FlexSensorGloveNotification,
+    time: DateTime<Local>,
+) -> FlexSensorGloveNotification {
+    let total = (to.dt - from.dt).num_microseconds().unwrap_or(1) as f64;
+    let elapsed = (time - from.dt).num_microseconds().unwrap_or(0) as f64;
+    let ratio = (elapsed / total).clamp(0.0, 1.0);
+
+    let mut flex_values = FingersFlexValues([0; 5]);
+    for (i, value) in flex_values.0.iter_mut().enumerate() {
+        let from_value = from.flex_values.0[i] as f64;
+        let to_value = to.flex_values.0[i] as f64;
+
+        *value = (from_value + (to_value - from_value) * ratio).round() as u32;
+    }
+
+    FlexSensorGloveNotification {
+        dt: time,
+        flex_values,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn notification(ms: i64, value: u32) -> FlexSensorGloveNotification {
+        FlexSensorGloveNotification {
+            dt: Local.timestamp_millis_opt(1_000_000 + ms).unwrap(),
+            flex_values: FingersFlexValues([value; 5]),
+        }
+    }
+
+    #[test]
+    fn interpolates_onto_the_grid() {
+        let mut resampler = Resampler::new(50.0, 200);
+
+        let (samples, gap) = resampler.push(notification(0, 0));
+        assert_eq!(samples.len(), 1);
+        assert!(gap.is_none());
+
+        let (samples, gap) = resampler.push(notification(50, 500));
+        assert!(gap.is_none());
+        let values: Vec<u32> = samples.iter().map(|s| s.flex_values.0[0]).collect();
+        assert_eq!(values, [200, 400]);
+        assert_eq!(samples[1].dt, notification(40, 0).dt);
+    }
+
+    #[test]
+    fn drops_out_of_order_notifications() {
+        let mut resampler = Resampler::new(50.0, 200);
+        resampler.push(notification(100, 0));
+
+        let (samples, gap) = resampler.push(notification(50, 100));
+        assert!(samples.is_empty());
+        assert!(gap.is_none());
+    }
+
+    #[test]
+    fn long_gap_restarts_the_grid() {
+        let mut resampler = Resampler::new(50.0, 200);
+        resampler.push(notification(0, 0));
+
+        let (samples, gap) = resampler.push(notification(500, 100));
+        let gap = gap.unwrap();
+        assert_eq!(gap.start, notification(0, 0).dt);
+        assert_eq!(gap.end, notification(500, 0).dt);
+        // The gap is not filled, the notification after it starts the new grid
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].dt, notification(500, 0).dt);
+
+        let (samples, _) = resampler.push(notification(520, 100));
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].dt, notification(520, 0).dt);
+    }
+
+    #[test]
+    fn gap_within_the_limit_is_filled() {
+        let mut resampler = Resampler::new(50.0, 200);
+        resampler.push(notification(0, 0));
+
+        let (samples, gap) = resampler.push(notification(200, 100));
+        assert!(gap.is_none());
+        assert_eq!(samples.len(), 10);
+    }
+}
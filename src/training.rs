@@ -0,0 +1,408 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{CharacterTable, Symbol, TextEncoder};
+
+/// A character typed by the participant while a target was presented
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingAttempt {
+    pub target: String,
+    pub expected: String,
+    pub typed: String,
+    /// Time since the target was presented or since the previous character was typed
+    pub latency_ms: i64,
+}
+
+impl TrainingAttempt {
+    /// Letters are compared regardless of their case, the character tables being uppercase
+    pub fn is_correct(&self) -> bool {
+        self.expected.to_lowercase() == self.typed.to_lowercase()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterStats {
+    pub character: String,
+    pub attempts: u32,
+    pub correct: u32,
+    pub accuracy: f64,
+    pub mean_latency_ms: f64,
+}
+
+/// How many times `expected` was typed as `typed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Confusion {
+    pub expected: String,
+    pub typed: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingReport {
+    pub participant: String,
+    pub started_at: DateTime<Local>,
+    pub finished_at: DateTime<Local>,
+    pub character_table: String,
+    pub attempts: Vec<TrainingAttempt>,
+    pub accuracy: f64,
+    pub characters: Vec<CharacterStats>,
+    pub confusions: Vec<Confusion>,
+}
+
+impl TrainingReport {
+    fn new(
+        participant: String,
+        character_table: String,
+        started_at: DateTime<Local>,
+        finished_at: DateTime<Local>,
+        attempts: Vec<TrainingAttempt>,
+    ) -> Self {
+        let mut characters: BTreeMap<String, (u32, u32, i64)> = BTreeMap::new();
+        let mut confusions: BTreeMap<(String, String), u32> = BTreeMap::new();
+
+        for attempt in &attempts {
+            let expected = attempt.expected.to_uppercase();
+            let stats = characters.entry(expected.clone()).or_default();
+            stats.0 += 1;
+            stats.2 += attempt.latency_ms;
+
+            if attempt.is_correct() {
+                stats.1 += 1;
+            } else {
+                *confusions
+                    .entry((expected, attempt.typed.to_uppercase()))
+                    .or_default() += 1;
+            }
+        }
+
+        let correct = attempts
+            .iter()
+            .filter(|attempt| attempt.is_correct())
+            .count();
+
+        let mut confusions: Vec<_> = confusions
+            .into_iter()
+            .map(|((expected, typed), count)| Confusion {
+                expected,
+                typed,
+                count,
+            })
+            .collect();
+        confusions.sort_by_key(|confusion| std::cmp::Reverse(confusion.count));
+
+        Self {
+            participant,
+            started_at,
+            finished_at,
+            character_table,
+            accuracy: ratio(correct as u32, attempts.len() as u32),
+            characters: characters
+                .into_iter()
+                .map(
+                    |(character, (attempts, correct, latency_ms))| CharacterStats {
+                        character,
+                        attempts,
+                        correct,
+                        accuracy: ratio(correct, attempts),
+                        mean_latency_ms: latency_ms as f64 / attempts as f64,
+                    },
+                )
+                .collect(),
+            confusions,
+            attempts,
+        }
+    }
+}
+
+fn ratio(count: u32, total: u32) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    count as f64 / total as f64
+}
+
+/// All the training sessions of a participant, to follow their progress over the weeks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingHistory {
+    pub participant: String,
+    pub sessions: Vec<TrainingReport>,
+}
+
+impl TrainingHistory {
+    /// Returns an empty history when the participant has not trained yet
+    pub fn load_or_default(path: &Path, participant: &str) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self {
+                participant: participant.to_string(),
+                sessions: vec![],
+            });
+        }
+
+        Self::load(path)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("unable to open training history {}", path.display()))?;
+
+        serde_json::from_reader(file)
+            .with_context(|| format!("invalid training history {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("unable to create training history {}", path.display()))?;
+
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Adds the report to the history of its participant stored in `folder`
+    pub fn append(folder: &Path, report: TrainingReport) -> anyhow::Result<PathBuf> {
+        let path = Self::default_path(folder, &report.participant)?;
+        let mut history = Self::load_or_default(&path, &report.participant)?;
+
+        history.sessions.push(report);
+        history.save(&path)?;
+
+        Ok(path)
+    }
+
+    pub fn default_path(folder: &Path, participant: &str) -> anyhow::Result<PathBuf> {
+        Self::validate_participant(participant)?;
+
+        Ok(folder.join(format!("{participant}.training.json")))
+    }
+
+    /// The participant is used as a file name, it cannot point to another folder
+    pub fn validate_participant(participant: &str) -> anyhow::Result<()> {
+        if participant.is_empty()
+            || participant.contains(['/', '\\'])
+            || participant == "."
+            || participant == ".."
+        {
+            bail!(
+                "invalid participant {participant:?}, it must be a file name without path separators"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TrainingEvent {
+    TargetPresented {
+        target: String,
+        index: usize,
+        count: usize,
+    },
+    CharacterScored {
+        #[serde(flatten)]
+        attempt: TrainingAttempt,
+        correct: bool,
+    },
+    Finished(Box<TrainingReport>),
+}
+
+pub type TrainingEventFn = Box<dyn Fn(&TrainingEvent) + Send + Sync>;
+
+struct TrainingTarget {
+    text: String,
+    /// Characters as emitted by `TextPattern`, a symbol like "12" being typed at once
+    characters: Vec<String>,
+}
+
+/// Presents target characters or words one after the other and scores the characters
+/// typed by the participant against them
+pub struct TrainingSession {
+    participant: String,
+    character_table: String,
+
+    targets: Vec<TrainingTarget>,
+    current_target: usize,
+    current_character: usize,
+
+    started_at: Option<DateTime<Local>>,
+    expected_since: DateTime<Local>,
+    attempts: Vec<TrainingAttempt>,
+
+    on_event: Option<TrainingEventFn>,
+}
+
+impl TrainingSession {
+    pub fn new(
+        participant: String,
+        targets: &[String],
+        character_table: &CharacterTable,
+    ) -> anyhow::Result<Self> {
+        if targets.is_empty() {
+            bail!("a training session needs at least one target");
+        }
+
+        // The history of the participant is saved under their name at the end of the session
+        TrainingHistory::validate_participant(&participant)?;
+
+        let encoder = TextEncoder::new(character_table.clone());
+        let targets = targets
+            .iter()
+            .map(|target| {
+                if target.trim().is_empty() {
+                    bail!("the training target {target:?} has no character to type");
+                }
+
+                let characters = encoder
+                    .encode(target)
+                    .with_context(|| format!("invalid training target {target:?}"))?
+                    .into_iter()
+                    .map(|symbol| symbol.text)
+                    .collect();
+
+                Ok(TrainingTarget {
+                    text: target.clone(),
+                    characters,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            participant,
+            character_table: character_table.name.clone(),
+
+            targets,
+            current_target: 0,
+            current_character: 0,
+
+            started_at: None,
+            expected_since: Local::now(),
+            attempts: vec![],
+
+            on_event: None,
+        })
+    }
+
+    /// Picks `count` characters of the default layer of the table
+    pub fn random_characters(character_table: &CharacterTable, count: usize) -> Vec<String> {
+        let characters: Vec<_> = character_table.layers[0]
+            .symbols
+            .iter()
+            .filter_map(|symbol| match symbol {
+                Symbol::Text(text) if text.chars().any(char::is_alphanumeric) => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let mut rng = rand::thread_rng();
+        (0..count)
+            .filter_map(|_| characters.choose(&mut rng).cloned())
+            .collect()
+    }
+
+    pub fn on_event(&mut self, closure: impl Fn(&TrainingEvent) + Send + Sync + 'static) {
+        self.on_event = Some(Box::new(closure))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.current_target >= self.targets.len()
+    }
+
+    /// Presents the first target on the first call, then scores the characters typed since
+    pub fn process(&mut self, typed: &[String], time: DateTime<Local>) {
+        if self.is_done() {
+            return;
+        }
+
+        if self.started_at.is_none() {
+            self.started_at = Some(time);
+            self.present_target(time);
+        }
+
+        for typed in typed {
+            // A correction does not type anything, the mistake it erases was already scored
+            if typed == "\u{8}" {
+                continue;
+            }
+
+            self.score(typed, time);
+
+            if self.is_done() {
+                return;
+            }
+        }
+    }
+
+    fn score(&mut self, typed: &str, time: DateTime<Local>) {
+        let target = &self.targets[self.current_target];
+        let attempt = TrainingAttempt {
+            target: target.text.clone(),
+            expected: target.characters[self.current_character].clone(),
+            typed: typed.to_string(),
+            latency_ms: time
+                .signed_duration_since(self.expected_since)
+                .num_milliseconds(),
+        };
+
+        // Mistakes are scored and skipped so that a session always ends
+        self.emit(&TrainingEvent::CharacterScored {
+            correct: attempt.is_correct(),
+            attempt: attempt.clone(),
+        });
+        self.attempts.push(attempt);
+        self.expected_since = time;
+        self.current_character += 1;
+
+        if self.current_character < target.characters.len() {
+            return;
+        }
+
+        self.current_target += 1;
+        self.current_character = 0;
+
+        if !self.is_done() {
+            self.present_target(time);
+            return;
+        }
+
+        let report = TrainingReport::new(
+            self.participant.clone(),
+            self.character_table.clone(),
+            self.started_at.unwrap_or(time),
+            time,
+            std::mem::take(&mut self.attempts),
+        );
+        self.emit(&TrainingEvent::Finished(Box::new(report)));
+    }
+
+    fn present_target(&mut self, time: DateTime<Local>) {
+        self.expected_since = time;
+        self.emit(&TrainingEvent::TargetPresented {
+            target: self.targets[self.current_target].text.clone(),
+            index: self.current_target,
+            count: self.targets.len(),
+        });
+    }
+
+    fn emit(&self, event: &TrainingEvent) {
+        if let Some(on_event) = self.on_event.as_ref() {
+            on_event(event)
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{GateState, ProcessEvent, ReceivedMessage, TimedEvent};
+
+/// What the transcript keeps of the process events
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TranscriptEntry {
+    Character {
+        text: String,
+        confidence: Option<f32>,
+    },
+    Backspace,
+    /// The next characters are typed in `layer`, `None` being the default layer
+    LayerChanged {
+        layer: Option<String>,
+    },
+    Word {
+        raw: String,
+        corrected: String,
+    },
+    Pattern {
+        name: String,
+        repetitions: u32,
+    },
+    Marker {
+        label: String,
+    },
+    DecoderArmed,
+    DecoderDisarmed,
+    /// The activity gate suspended pattern decoding
+    DecodingSuspended,
+    DecodingResumed,
+    MessageStarted,
+    MessageReceived(ReceivedMessage),
+}
+
+impl Display for TranscriptEntry {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TranscriptEntry::Character {
+                text,
+                confidence: Some(confidence),
+            } => write!(f, "typed {text:?} (confidence {confidence:.2})"),
+            TranscriptEntry::Character { text, .. } => write!(f, "typed {text:?}"),
+            TranscriptEntry::Backspace => write!(f, "backspace"),
+            TranscriptEntry::LayerChanged { layer: Some(layer) } => write!(f, "layer {layer}"),
+            TranscriptEntry::LayerChanged { layer: None } => write!(f, "default layer"),
+            TranscriptEntry::Word { raw, corrected } if raw != corrected => {
+                write!(f, "word {raw:?} corrected to {corrected:?}")
+            }
+            TranscriptEntry::Word { raw, .. } => write!(f, "word {raw:?}"),
+            TranscriptEntry::Pattern { name, repetitions } => {
+                write!(f, "pattern {name} x{repetitions}")
+            }
+            TranscriptEntry::Marker { label } => write!(f, "marker {label}"),
+            TranscriptEntry::DecoderArmed => write!(f, "decoder armed"),
+            TranscriptEntry::DecoderDisarmed => write!(f, "decoder disarmed"),
+            TranscriptEntry::DecodingSuspended => write!(f, "decoding suspended"),
+            TranscriptEntry::DecodingResumed => write!(f, "decoding resumed"),
+            TranscriptEntry::MessageStarted => write!(f, "message started"),
+            TranscriptEntry::MessageReceived(message) => {
+                let status = match message.verified {
+                    true => "verified",
+                    false => "not verified",
+                };
+                write!(f, "message {:?} {status}", message.text)
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TranscriptLine<'a> {
+    dt: &'a DateTime<Local>,
+    #[serde(flatten)]
+    entry: &'a TranscriptEntry,
+}
+
+/// Records what was decoded during a session, with the time of the sample it was decoded from,
+/// as JSON Lines and as plain text
+pub struct TranscriptWriter {
+    json_lines: BufWriter<File>,
+    text: BufWriter<File>,
+
+    layer: Option<String>,
+}
+
+impl TranscriptWriter {
+    /// Writes `path` with the `jsonl` extension and its plain text rendering with the `txt` one
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let (json_lines_path, text_path) = Self::paths(path);
+
+        let json_lines = File::create(&json_lines_path)
+            .with_context(|| format!("unable to create {}", json_lines_path.display()))?;
+        let text = File::create(&text_path)
+            .with_context(|| format!("unable to create {}", text_path.display()))?;
+
+        Ok(Self {
+            json_lines: BufWriter::new(json_lines),
+            text: BufWriter::new(text),
+
+            layer: None,
+        })
+    }
+
+    pub fn paths(path: &Path) -> (PathBuf, PathBuf) {
+        (path.with_extension("jsonl"), path.with_extension("txt"))
+    }
+
+    /// Events that are not part of the transcript are ignored
+    pub fn write_event(&mut self, event: &TimedEvent) -> anyhow::Result<()> {
+        let Some(entry) = self.entry(&event.event) else {
+            return Ok(());
+        };
+
+        serde_json::to_writer(
+            &mut self.json_lines,
+            &TranscriptLine {
+                dt: &event.dt,
+                entry: &entry,
+            },
+        )?;
+        writeln!(self.json_lines)?;
+        writeln!(
+            self.text,
+            "{} {entry}",
+            event.dt.format("%Y-%m-%d %H:%M:%S%.3f")
+        )?;
+
+        // Keeps the transcript if the session ends abruptly
+        self.json_lines.flush()?;
+        self.text.flush()?;
+
+        Ok(())
+    }
+
+    fn entry(&mut self, event: &ProcessEvent) -> Option<TranscriptEntry> {
+        let entry = match event {
+            ProcessEvent::CharacterTyped(character) if character.text == "\u{8}" => {
+                TranscriptEntry::Backspace
+            }
+            ProcessEvent::CharacterTyped(character) => TranscriptEntry::Character {
+                text: character.text.clone(),
+                confidence: character.confidence,
+            },
+            ProcessEvent::TextProgress(progress) if progress.layer != self.layer => {
+                self.layer = progress.layer.clone();
+                TranscriptEntry::LayerChanged {
+                    layer: progress.layer.clone(),
+                }
+            }
+            ProcessEvent::WordDecoded { raw, corrected } => TranscriptEntry::Word {
+                raw: raw.clone(),
+                corrected: corrected.clone(),
+            },
+            ProcessEvent::PatternDetected { name, repetitions } => TranscriptEntry::Pattern {
+                name: name.clone(),
+                repetitions: *repetitions,
+            },
+            ProcessEvent::Marker { label } => TranscriptEntry::Marker {
+                label: label.clone(),
+            },
+            ProcessEvent::DecoderArmed { .. } => TranscriptEntry::DecoderArmed,
+            ProcessEvent::DecoderDisarmed => TranscriptEntry::DecoderDisarmed,
+            ProcessEvent::GateChanged {
+                state: GateState::Active,
+                ..
+            } => TranscriptEntry::DecodingSuspended,
+            ProcessEvent::GateChanged {
+                state: GateState::Quiet,
+                ..
+            } => TranscriptEntry::DecodingResumed,
+            ProcessEvent::MessageStarted => TranscriptEntry::MessageStarted,
+            ProcessEvent::MessageReceived(message) => {
+                TranscriptEntry::MessageReceived(message.clone())
+            }
+            _ => return None,
+        };
+
+        Some(entry)
+    }
+}
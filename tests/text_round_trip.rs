@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use chrono::Local;
+use clap::Parser;
+use cofield_receiver::{
+    CharacterTable, Opt, Process, ProcessEvent, TextEncoder, TextPattern, DEFAULT_RESAMPLE_RATE,
+};
+use futures::StreamExt;
+use tokio::sync::Mutex;
+
+/// Types the text with the encoder and decodes the synthetic glove notifications
+/// with the same processing as `--input-from-stdin --decode-text`
+async fn round_trip(text: &str) -> anyhow::Result<String> {
+    let opt = Opt::parse_from(["cofield-receiver"]);
+
+    let encoder = TextEncoder::new(CharacterTable::default());
+    let symbols = encoder.encode(text)?;
+    let presses = encoder.presses(&symbols);
+    let notifications =
+        encoder.synthetic_notifications(&presses, Local::now(), DEFAULT_RESAMPLE_RATE);
+
+    let notification_stream = futures::stream::iter(notifications).boxed();
+    let mut process = Process::new(notification_stream, opt.get_fingers_sensibility()?).await;
+    process.set_movement_detector(opt.get_movement_detector()?);
+    process.set_aggregator(Arc::new(Mutex::new(opt.get_mean_aggregator())));
+
+    let mut text_pattern = TextPattern::new(Box::new(|_| {}));
+    text_pattern.set_character_table(CharacterTable::default());
+    process.set_text_pattern_detection(Arc::new(Mutex::new(Some(text_pattern))));
+
+    let decoded = Arc::new(std::sync::Mutex::new(String::new()));
+    let decoded_events = decoded.clone();
+    process.on_event(move |event| {
+        if let ProcessEvent::CharacterTyped(character) = &event.event {
+            decoded_events.lock().unwrap().push_str(&character.text);
+        }
+    });
+
+    process.run().await?;
+
+    let decoded = decoded.lock().unwrap().clone();
+    Ok(decoded)
+}
+
+#[tokio::test]
+async fn letters_are_decoded() {
+    assert_eq!(round_trip("hello").await.unwrap(), "HELLO");
+}
+
+#[tokio::test]
+async fn layer_switch_is_decoded() {
+    let symbols = TextEncoder::new(CharacterTable::default())
+        .encode("7")
+        .unwrap();
+    assert_eq!(
+        symbols[0].codes.len(),
+        2,
+        "the number is typed in another layer"
+    );
+
+    assert_eq!(round_trip("hi 7").await.unwrap(), "HI 7");
+}
+
+#[tokio::test]
+async fn single_code_is_decoded_after_the_timeout() {
+    let symbols = TextEncoder::new(CharacterTable::default())
+        .encode("a")
+        .unwrap();
+    assert!(symbols[0].codes[0].waits_for_timeout());
+
+    assert_eq!(round_trip("ba").await.unwrap(), "BA");
+}